@@ -14,9 +14,15 @@ use std::vec::Vec;
 
 use super::format::unescape;
 use super::statistics::ChannelStatistics;
+use crate::lexer::markup::LexerMarkup;
+use crate::lexer::synonyms::LexerSynonyms;
+use crate::lexer::token::TokenLexerBuilder;
 use crate::query::builder::{QueryBuilder, QueryBuilderResult};
+use crate::query::cache::QueryCache;
 use crate::query::types::{
-    ListMetaData, QueryGenericLang, QueryMetaData, QuerySearchLimit, QuerySearchOffset,
+    LexMetaData, ListMetaData, PushMetaData, QueryGenericLang, QueryListOptions, QueryMetaData,
+    QueryPushFormat, QueryPushSort, QuerySearchCursor, QuerySearchLimit, QuerySearchOffset,
+    QuerySearchOptions, QuerySearchOrder, QuerySearchRange, QuerySearchWildcard, QuerySearchWith,
 };
 use crate::store::fst::StoreFSTPool;
 use crate::store::kv::StoreKVPool;
@@ -34,6 +40,7 @@ pub enum ChannelCommandError {
     InvalidFormat(&'static str),
     InvalidMetaKey((String, String)),
     InvalidMetaValue((String, String)),
+    InvalidTerm(String),
 }
 
 #[derive(PartialEq)]
@@ -70,13 +77,15 @@ static BACKUP_FST_PATH: &str = "fst";
 
 lazy_static! {
     pub static ref COMMANDS_MODE_SEARCH: Vec<&'static str> =
-        vec!["QUERY", "SUGGEST", "LIST", "PING", "HELP", "QUIT"];
-    pub static ref COMMANDS_MODE_INGEST: Vec<&'static str> =
-        vec!["PUSH", "POP", "COUNT", "FLUSHC", "FLUSHB", "FLUSHO", "PING", "HELP", "QUIT"];
+        vec!["QUERY", "EXPLAIN", "SUGGEST", "CORRECT", "LIST", "LEX", "PING", "HELP", "QUIT",];
+    pub static ref COMMANDS_MODE_INGEST: Vec<&'static str> = vec![
+        "PUSH", "POP", "REPLACE", "COUNT", "FLUSHC", "FLUSHB", "FLUSHO", "LEX", "PING", "HELP",
+        "QUIT",
+    ];
     pub static ref COMMANDS_MODE_CONTROL: Vec<&'static str> =
         vec!["TRIGGER", "INFO", "PING", "HELP", "QUIT"];
     pub static ref CONTROL_TRIGGER_ACTIONS: Vec<&'static str> =
-        vec!["consolidate", "backup", "restore"];
+        vec!["consolidate", "backup", "restore", "synonyms"];
     static ref MANUAL_MODE_SEARCH: HashMap<&'static str, &'static Vec<&'static str>> =
         [("commands", &*COMMANDS_MODE_SEARCH)]
             .iter()
@@ -129,6 +138,78 @@ impl ChannelCommandBase {
         }
     }
 
+    pub fn dispatch_lex(mut parts: SplitWhitespace) -> ChannelResult {
+        match Self::parse_text_parts(&mut parts) {
+            Some(text) => {
+                debug!("dispatching lex for text: {}", text);
+
+                // Define lex parameters
+                let (mut lex_lang, mut lex_collection, mut lex_format) =
+                    (None, None, QueryPushFormat::Text);
+
+                // Parse meta parts (meta comes after text; extract meta parts second)
+                let mut last_meta_err = None;
+
+                while let Some(meta_result) = Self::parse_next_meta_parts(&mut parts) {
+                    match Self::handle_lex_meta(meta_result) {
+                        Ok(LexMetaData::Lang(lex_lang_parsed)) => lex_lang = Some(lex_lang_parsed),
+                        Ok(LexMetaData::Collection(lex_collection_parsed)) => {
+                            lex_collection = Some(lex_collection_parsed)
+                        }
+                        Ok(LexMetaData::Format(lex_format_parsed)) => {
+                            lex_format = lex_format_parsed
+                        }
+                        Err(parse_err) => last_meta_err = Some(parse_err),
+                    }
+                }
+
+                if let Some(err) = last_meta_err {
+                    Err(err)
+                } else {
+                    debug!(
+                        "will lex text: {} with hinted locale: <{:?}> in collection: <{:?}> \
+                            and format: {:?}",
+                        text, lex_lang, lex_collection, lex_format
+                    );
+
+                    // Strip markup from text (if any format is set, eg. HTML)
+                    let text = LexerMarkup::strip(&text, lex_format);
+
+                    // Lex text in the same way as 'push' does (no store gets touched there)
+                    let mut lexer = TokenLexerBuilder::from_query_lang(
+                        lex_lang,
+                        lex_collection.as_deref().unwrap_or(""),
+                        &text,
+                    )
+                    .or(Err(ChannelCommandError::QueryError))?;
+
+                    lexer.trace_dropped();
+                    lexer.index_transliterations();
+
+                    let terms = lexer
+                        .by_ref()
+                        .map(|(term, term_hashed)| format!("{}:{}", term, term_hashed))
+                        .collect::<Vec<String>>();
+
+                    Ok(vec![ChannelCommandResponse::Result(format!(
+                        "locale({}) method({}) terms({}) dropped({})",
+                        lexer.locale().map(|locale| locale.code()).unwrap_or("none"),
+                        lexer.locale_method().as_str(),
+                        terms.join(","),
+                        lexer
+                            .dropped()
+                            .map(|dropped| dropped.join(","))
+                            .unwrap_or_default()
+                    ))])
+                }
+            }
+            _ => Err(ChannelCommandError::InvalidFormat(
+                "LEX \"<text>\" [LANG(<locale>)]? [COLLECTION(<collection>)]? \
+                 [FORMAT(<format>)]?",
+            )),
+        }
+    }
+
     pub fn generic_dispatch_help(
         mut parts: SplitWhitespace,
         manuals: &HashMap<&str, &Vec<&str>>,
@@ -288,6 +369,43 @@ impl ChannelCommandBase {
         }
     }
 
+    fn handle_lex_meta(meta_result: MetaPartsResult) -> Result<LexMetaData, ChannelCommandError> {
+        match meta_result {
+            Ok((meta_key, meta_value)) => {
+                debug!("handle lex meta: {} = {}", meta_key, meta_value);
+
+                match meta_key {
+                    "LANG" => {
+                        // 'LANG(<locale>)' where <locale> ∈ ISO 639-3
+                        if let Some(query_lang_parsed) = QueryGenericLang::from_value(meta_value) {
+                            Ok(LexMetaData::Lang(query_lang_parsed))
+                        } else {
+                            Err(Self::make_error_invalid_meta_value(meta_key, meta_value))
+                        }
+                    }
+                    "COLLECTION" => {
+                        // 'COLLECTION(<collection>)' where <collection> is a collection name
+                        if !meta_value.is_empty() {
+                            Ok(LexMetaData::Collection(meta_value.to_owned()))
+                        } else {
+                            Err(Self::make_error_invalid_meta_value(meta_key, meta_value))
+                        }
+                    }
+                    "FORMAT" => {
+                        // 'FORMAT(<format>)' where <format> ∈ {html, markdown, text}
+                        if let Some(lex_format_parsed) = QueryPushFormat::from_value(meta_value) {
+                            Ok(LexMetaData::Format(lex_format_parsed))
+                        } else {
+                            Err(Self::make_error_invalid_meta_value(meta_key, meta_value))
+                        }
+                    }
+                    _ => Err(Self::make_error_invalid_meta_key(meta_key, meta_value)),
+                }
+            }
+            Err(err) => Err(Self::make_error_invalid_meta_key(err.0, err.1)),
+        }
+    }
+
     pub fn make_error_invalid_meta_key(meta_key: &str, meta_value: &str) -> ChannelCommandError {
         ChannelCommandError::InvalidMetaKey((meta_key.to_owned(), meta_value.to_owned()))
     }
@@ -370,63 +488,93 @@ impl ChannelCommandSearch {
                     event_id, collection, bucket
                 );
 
-                // Define query parameters
-                let (mut query_limit, mut query_offset, mut query_lang) =
-                    (APP_CONF.channel.search.query_limit_default, 0, None);
-
                 // Parse meta parts (meta comes after text; extract meta parts second)
-                let mut last_meta_err = None;
+                let (mut query_options, query_lang) = Self::parse_query_metas(&mut parts)?;
 
-                while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(&mut parts)
-                {
-                    match Self::handle_query_meta(meta_result) {
-                        Ok((Some(query_limit_parsed), None, None)) => {
-                            query_limit = query_limit_parsed
-                        }
-                        Ok((None, Some(query_offset_parsed), None)) => {
-                            query_offset = query_offset_parsed
-                        }
-                        Ok((None, None, Some(query_lang_parsed))) => {
-                            query_lang = Some(query_lang_parsed)
-                        }
-                        Err(parse_err) => last_meta_err = Some(parse_err),
-                        _ => {}
-                    }
-                }
+                // Extract wildcard terms from text (they do not go through the lexer, only \
+                //   their literal parts get normalized)
+                let (text, query_wildcards) =
+                    QuerySearchWildcard::extract_from_text(&text, collection)
+                        .map_err(|term| ChannelCommandError::InvalidTerm(term.to_string()))?;
 
-                if let Some(err) = last_meta_err {
-                    Err(err)
-                } else if query_limit < 1
-                    || query_limit > APP_CONF.channel.search.query_limit_maximum
-                {
-                    Err(ChannelCommandError::PolicyReject(
-                        "LIMIT out of minimum/maximum bounds",
-                    ))
-                } else {
-                    debug!(
-                        "will search for #{} with text: {}, limit: {}, offset: {}, locale: <{:?}>",
-                        event_id, text, query_limit, query_offset, query_lang
-                    );
+                query_options.wildcards = query_wildcards;
 
-                    // Commit 'search' query
-                    ChannelCommandBase::commit_pending_operation(
-                        "QUERY",
+                debug!(
+                    "will search for #{} with text: {}, options: {:?}, locale: <{:?}>",
+                    event_id, text, query_options, query_lang
+                );
+
+                // Commit 'search' query
+                ChannelCommandBase::commit_pending_operation(
+                    "QUERY",
+                    &event_id,
+                    QueryBuilder::search(
                         &event_id,
-                        QueryBuilder::search(
-                            &event_id,
-                            collection,
-                            bucket,
-                            &text,
-                            query_limit,
-                            query_offset,
-                            query_lang,
-                        ),
-                    )
-                }
+                        collection,
+                        bucket,
+                        &text,
+                        query_lang,
+                        query_options,
+                    ),
+                )
             }
             _ => Err(ChannelCommandError::InvalidFormat(
                 "QUERY <collection> <bucket> \"<terms>\" [LIMIT(<count>)]? [OFFSET(<count>)]? \
-                 [LANG(<locale>)]?",
+                 [LANG(<locale>)]? [ORDER(<key>,asc|desc)]? [RANGE(<key>,<min>,<max>)]? \
+                 [CURSOR(<cursor>)]? [WITH(matches|cursor|total)]?",
+            )),
+        }
+    }
+
+    pub fn dispatch_explain(mut parts: SplitWhitespace) -> ChannelResult {
+        match (
+            parts.next(),
+            parts.next(),
+            ChannelCommandBase::parse_text_parts(&mut parts),
+        ) {
+            (Some(collection), Some(bucket), Some(text)) => {
+                // Generate command identifier
+                let event_id = ChannelCommandBase::generate_event_id();
+
+                debug!(
+                    "dispatching search explain #{} on collection: {} and bucket: {}",
+                    event_id, collection, bucket
+                );
+
+                // Parse meta parts (explain takes the same meta parts as query)
+                let (mut query_options, query_lang) = Self::parse_query_metas(&mut parts)?;
+
+                // Extract wildcard terms from text (they do not go through the lexer, only \
+                //   their literal parts get normalized)
+                let (text, query_wildcards) =
+                    QuerySearchWildcard::extract_from_text(&text, collection)
+                        .map_err(|term| ChannelCommandError::InvalidTerm(term.to_string()))?;
+
+                query_options.wildcards = query_wildcards;
+
+                debug!(
+                    "will explain for #{} with text: {}, options: {:?}, locale: <{:?}>",
+                    event_id, text, query_options, query_lang
+                );
+
+                // Commit 'explain' query
+                ChannelCommandBase::commit_pending_operation(
+                    "EXPLAIN",
+                    &event_id,
+                    QueryBuilder::explain(
+                        &event_id,
+                        collection,
+                        bucket,
+                        &text,
+                        query_lang,
+                        query_options,
+                    ),
+                )
+            }
+            _ => Err(ChannelCommandError::InvalidFormat(
+                "EXPLAIN <collection> <bucket> \"<terms>\" [LIMIT(<count>)]? [OFFSET(<count>)]? \
+                 [LANG(<locale>)]? [ORDER(<key>,asc|desc)]? [RANGE(<key>,<min>,<max>)]? \
+                 [CURSOR(<cursor>)]? [WITH(matches|cursor|total)]?",
             )),
         }
     }
@@ -489,6 +637,57 @@ impl ChannelCommandSearch {
         }
     }
 
+    pub fn dispatch_correct(mut parts: SplitWhitespace) -> ChannelResult {
+        match (
+            parts.next(),
+            parts.next(),
+            ChannelCommandBase::parse_text_parts(&mut parts),
+        ) {
+            (Some(collection), Some(bucket), Some(text)) => {
+                // Generate command identifier
+                let event_id = ChannelCommandBase::generate_event_id();
+
+                debug!(
+                    "dispatching search correct #{} on collection: {} and bucket: {}",
+                    event_id, collection, bucket
+                );
+
+                // Define correct parameters
+                let mut correct_lang = None;
+
+                // Parse meta parts (meta comes after text; extract meta parts second)
+                let mut last_meta_err = None;
+
+                while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(&mut parts)
+                {
+                    match Self::handle_correct_meta(meta_result) {
+                        Ok(correct_lang_parsed) => correct_lang = Some(correct_lang_parsed),
+                        Err(parse_err) => last_meta_err = Some(parse_err),
+                    }
+                }
+
+                if let Some(err) = last_meta_err {
+                    Err(err)
+                } else {
+                    debug!(
+                        "will correct for #{} with text: {} and locale: <{:?}>",
+                        event_id, text, correct_lang
+                    );
+
+                    // Commit 'correct' query
+                    ChannelCommandBase::commit_pending_operation(
+                        "CORRECT",
+                        &event_id,
+                        QueryBuilder::correct(&event_id, collection, bucket, &text, correct_lang),
+                    )
+                }
+            }
+            _ => Err(ChannelCommandError::InvalidFormat(
+                "CORRECT <collection> <bucket> \"<terms>\" [LANG(<locale>)]?",
+            )),
+        }
+    }
+
     pub fn dispatch_list(mut parts: SplitWhitespace) -> ChannelResult {
         match (parts.next(), parts.next()) {
             (Some(collection), Some(bucket)) => {
@@ -501,8 +700,8 @@ impl ChannelCommandSearch {
                 );
 
                 // Define list parameters
-                let (mut list_limit, mut list_offset) =
-                    (APP_CONF.channel.search.list_limit_default, 0);
+                let mut list_options =
+                    QueryListOptions::new(APP_CONF.channel.search.list_limit_default, 0);
 
                 // Parse meta parts (meta comes last; extract meta parts second)
                 let mut last_meta_err = None;
@@ -510,18 +709,36 @@ impl ChannelCommandSearch {
                 while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(&mut parts)
                 {
                     match Self::handle_list_meta(meta_result) {
-                        Ok(metadata) => match metadata {
-                            (Some(list_limit_parsed), None) => list_limit = list_limit_parsed,
-                            (None, Some(list_offset_parsed)) => list_offset = list_offset_parsed,
-                            _ => {}
-                        },
+                        Ok(ListMetaData::Limit(list_limit_parsed)) => {
+                            list_options.limit = list_limit_parsed
+                        }
+                        Ok(ListMetaData::Offset(list_offset_parsed)) => {
+                            list_options.offset = list_offset_parsed
+                        }
+                        Ok(ListMetaData::Prefix(list_prefix_parsed)) => {
+                            list_options.prefix = Some(list_prefix_parsed)
+                        }
+                        Ok(ListMetaData::Match(list_pattern_parsed)) => {
+                            list_options.pattern = Some(list_pattern_parsed)
+                        }
+                        Ok(ListMetaData::Cursor(list_cursor_parsed)) => {
+                            list_options.cursor = Some(list_cursor_parsed)
+                        }
+                        Ok(ListMetaData::With(list_with_parsed)) => {
+                            for list_with in list_with_parsed {
+                                if list_with == QuerySearchWith::Cursor {
+                                    list_options.with_cursor = true
+                                }
+                            }
+                        }
                         Err(parse_err) => last_meta_err = Some(parse_err),
                     }
                 }
 
                 if let Some(err) = last_meta_err {
                     Err(err)
-                } else if list_limit < 1 || list_limit > APP_CONF.channel.search.list_limit_maximum
+                } else if list_options.limit < 1
+                    || list_options.limit > APP_CONF.channel.search.list_limit_maximum
                 {
                     Err(ChannelCommandError::PolicyReject(
                         "LIMIT out of minimum/maximum bounds",
@@ -531,12 +748,13 @@ impl ChannelCommandSearch {
                     ChannelCommandBase::commit_pending_operation(
                         "LIST",
                         &event_id,
-                        QueryBuilder::list(&event_id, collection, bucket, list_limit, list_offset),
+                        QueryBuilder::list(&event_id, collection, bucket, list_options),
                     )
                 }
             }
             _ => Err(ChannelCommandError::InvalidFormat(
-                "LIST <collection> <bucket> [LIMIT(<count>)]? [OFFSET(<count>)]?",
+                "LIST <collection> <bucket> [LIMIT(<count>)]? [OFFSET(<count>)]? \
+                 [PREFIX(<prefix>)]? [MATCH(<pattern>)]? [CURSOR(<cursor>)]? [WITH(cursor)]?",
             )),
         }
     }
@@ -545,6 +763,61 @@ impl ChannelCommandSearch {
         ChannelCommandBase::generic_dispatch_help(parts, &*MANUAL_MODE_SEARCH)
     }
 
+    fn parse_query_metas(
+        parts: &mut SplitWhitespace,
+    ) -> Result<(QuerySearchOptions, Option<QueryGenericLang>), ChannelCommandError> {
+        // Define query parameters
+        let (mut query_options, mut query_lang) = (
+            QuerySearchOptions::new(APP_CONF.channel.search.query_limit_default, 0),
+            None,
+        );
+
+        let mut last_meta_err = None;
+
+        while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(parts) {
+            match Self::handle_query_meta(meta_result) {
+                Ok(QueryMetaData::Limit(query_limit_parsed)) => {
+                    query_options.limit = query_limit_parsed
+                }
+                Ok(QueryMetaData::Offset(query_offset_parsed)) => {
+                    query_options.offset = query_offset_parsed
+                }
+                Ok(QueryMetaData::Lang(query_lang_parsed)) => query_lang = Some(query_lang_parsed),
+                Ok(QueryMetaData::Order(query_order_parsed)) => {
+                    query_options.order = Some(query_order_parsed)
+                }
+                Ok(QueryMetaData::Range(query_range_parsed)) => {
+                    query_options.range = Some(query_range_parsed)
+                }
+                Ok(QueryMetaData::Cursor(query_cursor_parsed)) => {
+                    query_options.cursor = Some(query_cursor_parsed)
+                }
+                Ok(QueryMetaData::With(query_with_parsed)) => {
+                    for query_with in query_with_parsed {
+                        match query_with {
+                            QuerySearchWith::Matches => query_options.with_matches = true,
+                            QuerySearchWith::Cursor => query_options.with_cursor = true,
+                            QuerySearchWith::Total => query_options.with_total = true,
+                        }
+                    }
+                }
+                Err(parse_err) => last_meta_err = Some(parse_err),
+            }
+        }
+
+        if let Some(err) = last_meta_err {
+            Err(err)
+        } else if query_options.limit < 1
+            || query_options.limit > APP_CONF.channel.search.query_limit_maximum
+        {
+            Err(ChannelCommandError::PolicyReject(
+                "LIMIT out of minimum/maximum bounds",
+            ))
+        } else {
+            Ok((query_options, query_lang))
+        }
+    }
+
     fn handle_query_meta(
         meta_result: MetaPartsResult,
     ) -> Result<QueryMetaData, ChannelCommandError> {
@@ -556,7 +829,7 @@ impl ChannelCommandSearch {
                     "LIMIT" => {
                         // 'LIMIT(<count>)' where 0 <= <count> < 2^16
                         if let Ok(query_limit_parsed) = meta_value.parse::<QuerySearchLimit>() {
-                            Ok((Some(query_limit_parsed), None, None))
+                            Ok(QueryMetaData::Limit(query_limit_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
@@ -566,7 +839,7 @@ impl ChannelCommandSearch {
                     "OFFSET" => {
                         // 'OFFSET(<count>)' where 0 <= <count> < 2^32
                         if let Ok(query_offset_parsed) = meta_value.parse::<QuerySearchOffset>() {
-                            Ok((None, Some(query_offset_parsed), None))
+                            Ok(QueryMetaData::Offset(query_offset_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
@@ -576,7 +849,48 @@ impl ChannelCommandSearch {
                     "LANG" => {
                         // 'LANG(<locale>)' where <locale> ∈ ISO 639-3
                         if let Some(query_lang_parsed) = QueryGenericLang::from_value(meta_value) {
-                            Ok((None, None, Some(query_lang_parsed)))
+                            Ok(QueryMetaData::Lang(query_lang_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "ORDER" => {
+                        // 'ORDER(<key>,<direction>)' where <direction> ∈ {asc, desc}
+                        if let Some(query_order_parsed) = QuerySearchOrder::from_value(meta_value) {
+                            Ok(QueryMetaData::Order(query_order_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "RANGE" => {
+                        // 'RANGE(<key>,<min>,<max>)' where -2^63 <= <min> <= <max> < 2^63
+                        if let Some(query_range_parsed) = QuerySearchRange::from_value(meta_value) {
+                            Ok(QueryMetaData::Range(query_range_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "CURSOR" => {
+                        // 'CURSOR(<cursor>)' where <cursor> is an opaque value from a prior page
+                        if let Some(query_cursor_parsed) = QuerySearchCursor::from_value(meta_value)
+                        {
+                            Ok(QueryMetaData::Cursor(query_cursor_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "WITH" => {
+                        // 'WITH(<flag>[,<flag>]*)' where <flag> ∈ {matches, cursor, total}
+                        if let Some(query_with_parsed) = QuerySearchWith::from_value(meta_value) {
+                            Ok(QueryMetaData::With(query_with_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
@@ -623,6 +937,35 @@ impl ChannelCommandSearch {
         }
     }
 
+    fn handle_correct_meta(
+        meta_result: MetaPartsResult,
+    ) -> Result<QueryGenericLang, ChannelCommandError> {
+        match meta_result {
+            Ok((meta_key, meta_value)) => {
+                debug!("handle correct meta: {} = {}", meta_key, meta_value);
+
+                match meta_key {
+                    "LANG" => {
+                        // 'LANG(<locale>)' where <locale> ∈ ISO 639-3
+                        if let Some(query_lang_parsed) = QueryGenericLang::from_value(meta_value) {
+                            Ok(query_lang_parsed)
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    _ => Err(ChannelCommandBase::make_error_invalid_meta_key(
+                        meta_key, meta_value,
+                    )),
+                }
+            }
+            Err(err) => Err(ChannelCommandBase::make_error_invalid_meta_key(
+                err.0, err.1,
+            )),
+        }
+    }
+
     fn handle_list_meta(meta_result: MetaPartsResult) -> Result<ListMetaData, ChannelCommandError> {
         match meta_result {
             Ok((meta_key, meta_value)) => {
@@ -632,7 +975,7 @@ impl ChannelCommandSearch {
                     "LIMIT" => {
                         // 'LIMIT(<count>)' where 0 <= <count> < 2^16
                         if let Ok(list_limit_parsed) = meta_value.parse::<QuerySearchLimit>() {
-                            Ok((Some(list_limit_parsed), None))
+                            Ok(ListMetaData::Limit(list_limit_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
@@ -642,13 +985,59 @@ impl ChannelCommandSearch {
                     "OFFSET" => {
                         // 'OFFSET(<count>)' where 0 <= <count> < 2^32
                         if let Ok(list_offset_parsed) = meta_value.parse::<QuerySearchOffset>() {
-                            Ok((None, Some(list_offset_parsed)))
+                            Ok(ListMetaData::Offset(list_offset_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "PREFIX" => {
+                        // 'PREFIX(<prefix>)' where <prefix> is the start of listed words
+                        if !meta_value.is_empty() {
+                            Ok(ListMetaData::Prefix(meta_value.to_owned()))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "MATCH" => {
+                        // 'MATCH(<pattern>)' where <pattern> is a regex that listed words match
+                        if !meta_value.is_empty() {
+                            Ok(ListMetaData::Match(meta_value.to_owned()))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "CURSOR" => {
+                        // 'CURSOR(<cursor>)' where <cursor> is an opaque value from a prior page
+                        if let Some(list_cursor_parsed) = QuerySearchCursor::from_value(meta_value)
+                        {
+                            Ok(ListMetaData::Cursor(list_cursor_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
                             ))
                         }
                     }
+                    "WITH" => {
+                        // 'WITH(<flag>[,<flag>]*)' where <flag> ∈ {cursor}
+                        match QuerySearchWith::from_value(meta_value) {
+                            Some(list_with_parsed)
+                                if list_with_parsed
+                                    .iter()
+                                    .all(|list_with| *list_with == QuerySearchWith::Cursor) =>
+                            {
+                                Ok(ListMetaData::With(list_with_parsed))
+                            }
+                            _ => Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            )),
+                        }
+                    }
                     _ => Err(ChannelCommandBase::make_error_invalid_meta_key(
                         meta_key, meta_value,
                     )),
@@ -676,37 +1065,26 @@ impl ChannelCommandIngest {
                 );
                 debug!("ingest push has text: {}", text);
 
-                // Define push parameters
-                let mut push_lang = None;
-
                 // Parse meta parts (meta comes after text; extract meta parts second)
-                let mut last_meta_err = None;
+                let (push_lang, push_sorts, push_format) = Self::parse_push_metas(&mut parts)?;
 
-                while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(&mut parts)
-                {
-                    match Self::handle_push_meta(meta_result) {
-                        Ok(Some(push_lang_parsed)) => push_lang = Some(push_lang_parsed),
-                        Err(parse_err) => last_meta_err = Some(parse_err),
-                        _ => {}
-                    }
-                }
+                debug!(
+                    "will push for text: {} with hinted locale: <{:?}>, sorts: {:?} and \
+                        format: {:?}",
+                    text, push_lang, push_sorts, push_format
+                );
 
-                if let Some(err) = last_meta_err {
-                    Err(err)
-                } else {
-                    debug!(
-                        "will push for text: {} with hinted locale: <{:?}>",
-                        text, push_lang
-                    );
+                // Strip markup from text (if any format is set, eg. HTML)
+                let text = LexerMarkup::strip(&text, push_format);
 
-                    // Commit 'push' query
-                    ChannelCommandBase::commit_ok_operation(QueryBuilder::push(
-                        collection, bucket, object, &text, push_lang,
-                    ))
-                }
+                // Commit 'push' query
+                ChannelCommandBase::commit_ok_operation(QueryBuilder::push(
+                    collection, bucket, object, &text, push_lang, push_sorts,
+                ))
             }
             _ => Err(ChannelCommandError::InvalidFormat(
-                "PUSH <collection> <bucket> <object> \"<text>\" [LANG(<locale>)]?",
+                "PUSH <collection> <bucket> <object> \"<text>\" [LANG(<locale>)]? \
+                 [SORT(<key>,<value>)]* [FORMAT(html|markdown|text)]?",
             )),
         }
     }
@@ -717,22 +1095,73 @@ impl ChannelCommandIngest {
             parts.next(),
             parts.next(),
             ChannelCommandBase::parse_text_parts(&mut parts),
-            parts.next(),
         ) {
-            (Some(collection), Some(bucket), Some(object), Some(text), None) => {
+            (Some(collection), Some(bucket), Some(object), Some(text)) => {
                 debug!(
                     "dispatching ingest pop in collection: {}, bucket: {} and object: {}",
                     collection, bucket, object
                 );
                 debug!("ingest pop has text: {}", text);
 
+                // Parse meta parts (meta comes after text; extract meta parts second)
+                let pop_lang = Self::parse_pop_metas(&mut parts)?;
+
+                debug!(
+                    "will pop with text: {} with hinted locale: <{:?}>",
+                    text, pop_lang
+                );
+
                 // Make 'pop' query
                 ChannelCommandBase::commit_result_operation(QueryBuilder::pop(
-                    collection, bucket, object, &text,
+                    collection, bucket, object, &text, pop_lang,
+                ))
+            }
+            _ => Err(ChannelCommandError::InvalidFormat(
+                "POP <collection> <bucket> <object> \"<text>\" [LANG(<locale>)]?",
+            )),
+        }
+    }
+
+    pub fn dispatch_replace(mut parts: SplitWhitespace) -> ChannelResult {
+        match (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            ChannelCommandBase::parse_text_parts(&mut parts),
+        ) {
+            (Some(collection), Some(bucket), Some(object), Some(text)) => {
+                debug!(
+                    "dispatching ingest replace in collection: {}, bucket: {} and object: {}",
+                    collection, bucket, object
+                );
+                debug!("ingest replace has text: {}", text);
+
+                // Parse meta parts (meta comes after text; extract meta parts second)
+                let (replace_lang, replace_sorts, replace_format) =
+                    Self::parse_push_metas(&mut parts)?;
+
+                debug!(
+                    "will replace with text: {} with hinted locale: <{:?}>, sorts: {:?} and \
+                        format: {:?}",
+                    text, replace_lang, replace_sorts, replace_format
+                );
+
+                // Strip markup from text (if any format is set, eg. HTML)
+                let text = LexerMarkup::strip(&text, replace_format);
+
+                // Commit 'replace' query
+                ChannelCommandBase::commit_ok_operation(QueryBuilder::replace(
+                    collection,
+                    bucket,
+                    object,
+                    &text,
+                    replace_lang,
+                    replace_sorts,
                 ))
             }
             _ => Err(ChannelCommandError::InvalidFormat(
-                "POP <collection> <bucket> <object> \"<text>\"",
+                "REPLACE <collection> <bucket> <object> \"<text>\" [LANG(<locale>)]? \
+                 [SORT(<key>,<value>)]* [FORMAT(html|markdown|text)]?",
             )),
         }
     }
@@ -812,9 +1241,90 @@ impl ChannelCommandIngest {
         ChannelCommandBase::generic_dispatch_help(parts, &*MANUAL_MODE_INGEST)
     }
 
-    fn handle_push_meta(
-        meta_result: MetaPartsResult,
+    fn parse_push_metas(
+        parts: &mut SplitWhitespace,
+    ) -> Result<
+        (
+            Option<QueryGenericLang>,
+            Vec<QueryPushSort>,
+            QueryPushFormat,
+        ),
+        ChannelCommandError,
+    > {
+        // Define push parameters
+        let (mut push_lang, mut push_sorts, mut push_format) =
+            (None, Vec::new(), QueryPushFormat::Text);
+
+        let mut last_meta_err = None;
+
+        while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(parts) {
+            match Self::handle_push_meta(meta_result) {
+                Ok(PushMetaData::Lang(push_lang_parsed)) => push_lang = Some(push_lang_parsed),
+                Ok(PushMetaData::Sort(push_sort_parsed)) => push_sorts.push(push_sort_parsed),
+                Ok(PushMetaData::Format(push_format_parsed)) => push_format = push_format_parsed,
+                Err(parse_err) => last_meta_err = Some(parse_err),
+            }
+        }
+
+        if let Some(err) = last_meta_err {
+            Err(err)
+        } else {
+            Ok((push_lang, push_sorts, push_format))
+        }
+    }
+
+    fn parse_pop_metas(
+        parts: &mut SplitWhitespace,
     ) -> Result<Option<QueryGenericLang>, ChannelCommandError> {
+        // Define pop parameters
+        let mut pop_lang = None;
+
+        let mut last_meta_err = None;
+
+        while let Some(meta_result) = ChannelCommandBase::parse_next_meta_parts(parts) {
+            match Self::handle_pop_meta(meta_result) {
+                Ok(pop_lang_parsed) => pop_lang = Some(pop_lang_parsed),
+                Err(parse_err) => last_meta_err = Some(parse_err),
+            }
+        }
+
+        if let Some(err) = last_meta_err {
+            Err(err)
+        } else {
+            Ok(pop_lang)
+        }
+    }
+
+    fn handle_pop_meta(
+        meta_result: MetaPartsResult,
+    ) -> Result<QueryGenericLang, ChannelCommandError> {
+        match meta_result {
+            Ok((meta_key, meta_value)) => {
+                debug!("handle pop meta: {} = {}", meta_key, meta_value);
+
+                match meta_key {
+                    "LANG" => {
+                        // 'LANG(<locale>)' where <locale> ∈ ISO 639-3
+                        if let Some(query_lang_parsed) = QueryGenericLang::from_value(meta_value) {
+                            Ok(query_lang_parsed)
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    _ => Err(ChannelCommandBase::make_error_invalid_meta_key(
+                        meta_key, meta_value,
+                    )),
+                }
+            }
+            Err(err) => Err(ChannelCommandBase::make_error_invalid_meta_key(
+                err.0, err.1,
+            )),
+        }
+    }
+
+    fn handle_push_meta(meta_result: MetaPartsResult) -> Result<PushMetaData, ChannelCommandError> {
         match meta_result {
             Ok((meta_key, meta_value)) => {
                 debug!("handle push meta: {} = {}", meta_key, meta_value);
@@ -823,7 +1333,27 @@ impl ChannelCommandIngest {
                     "LANG" => {
                         // 'LANG(<locale>)' where <locale> ∈ ISO 639-3
                         if let Some(query_lang_parsed) = QueryGenericLang::from_value(meta_value) {
-                            Ok(Some(query_lang_parsed))
+                            Ok(PushMetaData::Lang(query_lang_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "SORT" => {
+                        // 'SORT(<key>,<value>)' where -2^63 <= <value> < 2^63
+                        if let Some(push_sort_parsed) = QueryPushSort::from_value(meta_value) {
+                            Ok(PushMetaData::Sort(push_sort_parsed))
+                        } else {
+                            Err(ChannelCommandBase::make_error_invalid_meta_value(
+                                meta_key, meta_value,
+                            ))
+                        }
+                    }
+                    "FORMAT" => {
+                        // 'FORMAT(<format>)' where <format> ∈ {html, markdown, text}
+                        if let Some(push_format_parsed) = QueryPushFormat::from_value(meta_value) {
+                            Ok(PushMetaData::Format(push_format_parsed))
                         } else {
                             Err(ChannelCommandBase::make_error_invalid_meta_value(
                                 meta_key, meta_value,
@@ -886,9 +1416,15 @@ impl ChannelCommandControl {
                                 // Proceed KV + FST restore
                                 let path = Path::new(path);
 
-                                if StoreKVPool::restore(&path.join(BACKUP_KV_PATH)).is_ok()
-                                    && StoreFSTPool::restore(&path.join(BACKUP_FST_PATH)).is_ok()
-                                {
+                                let is_restored = StoreKVPool::restore(&path.join(BACKUP_KV_PATH))
+                                    .is_ok()
+                                    && StoreFSTPool::restore(&path.join(BACKUP_FST_PATH)).is_ok();
+
+                                // Cached query results are all outdated (even if restore failed, \
+                                //   as it may have partially restored data)
+                                QueryCache::clear();
+
+                                if is_restored {
                                     Ok(vec![ChannelCommandResponse::Ok])
                                 } else {
                                     Err(ChannelCommandError::InternalError)
@@ -897,6 +1433,21 @@ impl ChannelCommandControl {
                             _ => Err(ChannelCommandError::InvalidFormat("TRIGGER restore <path>")),
                         }
                     }
+                    "synonyms" => {
+                        if data_part.is_none() {
+                            // Reload all synonym dictionaries from their files
+                            if LexerSynonyms::load().is_ok() {
+                                // Cached query results may have been expanded w/ old synonyms
+                                QueryCache::clear();
+
+                                Ok(vec![ChannelCommandResponse::Ok])
+                            } else {
+                                Err(ChannelCommandError::InternalError)
+                            }
+                        } else {
+                            Err(ChannelCommandError::InvalidFormat("TRIGGER synonyms"))
+                        }
+                    }
                     _ => Err(ChannelCommandError::NotFound),
                 }
             }
@@ -911,7 +1462,9 @@ impl ChannelCommandControl {
                 Ok(vec![ChannelCommandResponse::Result(format!(
                     "uptime({}) clients_connected({}) commands_total({}) \
                      command_latency_best({}) command_latency_worst({}) \
-                     kv_open_count({}) fst_open_count({}) fst_consolidate_count({})",
+                     kv_open_count({}) fst_open_count({}) fst_consolidate_count({}) \
+                     query_cache_hits({}) query_cache_misses({}) query_cache_entries({}) \
+                     query_cache_size({})",
                     statistics.uptime,
                     statistics.clients_connected,
                     statistics.commands_total,
@@ -919,7 +1472,11 @@ impl ChannelCommandControl {
                     statistics.command_latency_worst,
                     statistics.kv_open_count,
                     statistics.fst_open_count,
-                    statistics.fst_consolidate_count
+                    statistics.fst_consolidate_count,
+                    statistics.query_cache_hits,
+                    statistics.query_cache_misses,
+                    statistics.query_cache_entries,
+                    statistics.query_cache_size
                 ))])
             }
             _ => Err(ChannelCommandError::InvalidFormat("INFO")),
@@ -947,6 +1504,7 @@ impl fmt::Display for ChannelCommandError {
             ChannelCommandError::InvalidMetaValue(ref data) => {
                 write!(f, "invalid_meta_value({}[{}])", data.0, data.1)
             }
+            ChannelCommandError::InvalidTerm(ref term) => write!(f, "invalid_term({})", term),
         }
     }
 }
@@ -966,5 +1524,47 @@ mod tests {
                 .0,
             "ERR"
         );
+        assert_eq!(
+            ChannelCommandError::InvalidTerm("/inv[/".to_string()).to_string(),
+            "invalid_term(/inv[/)"
+        );
+    }
+
+    #[test]
+    fn it_dispatches_lex() {
+        match ChannelCommandBase::dispatch_lex(
+            "\"The quick brown fox\" LANG(eng) COLLECTION(c:test:command)".split_whitespace(),
+        ) {
+            Ok(responses) => match responses.as_slice() {
+                [ChannelCommandResponse::Result(result)] => {
+                    assert!(result.starts_with("locale(eng) method(hinted) terms(quick:"));
+                    assert!(result.ends_with(") dropped(the)"));
+                }
+                _ => panic!("lex did not return a single result"),
+            },
+            Err(_) => panic!("lex failed"),
+        }
+
+        assert!(ChannelCommandBase::dispatch_lex("".split_whitespace()).is_err());
+        assert!(ChannelCommandBase::dispatch_lex("\"fox\" LANG(xxx)".split_whitespace()).is_err());
+        assert!(
+            ChannelCommandBase::dispatch_lex("\"fox\" COLLECTION()".split_whitespace()).is_err()
+        );
+        assert!(
+            ChannelCommandBase::dispatch_lex("\"fox\" FORMAT(xxx)".split_whitespace()).is_err()
+        );
+    }
+
+    #[test]
+    fn it_dispatches_lex_with_format() {
+        let (lex_html, lex_text) = (
+            ChannelCommandBase::dispatch_lex(
+                "\"<p>The <b>quick</b> fox</p>\" LANG(eng) FORMAT(html)".split_whitespace(),
+            ),
+            ChannelCommandBase::dispatch_lex("\"The quick fox\" LANG(eng)".split_whitespace()),
+        );
+
+        assert!(lex_html.is_ok());
+        assert!(lex_html.ok() == lex_text.ok());
     }
 }
@@ -165,8 +165,11 @@ impl ChannelMessageMode for ChannelMessageModeSearch {
     fn handle(message: &str) -> Result<Vec<ChannelCommandResponse>, ChannelCommandError> {
         gen_channel_message_mode_handle!(message, COMMANDS_MODE_SEARCH, {
             "QUERY" => ChannelCommandSearch::dispatch_query,
+            "EXPLAIN" => ChannelCommandSearch::dispatch_explain,
             "SUGGEST" => ChannelCommandSearch::dispatch_suggest,
+            "CORRECT" => ChannelCommandSearch::dispatch_correct,
             "LIST" => ChannelCommandSearch::dispatch_list,
+            "LEX" => ChannelCommandBase::dispatch_lex,
             "HELP" => ChannelCommandSearch::dispatch_help,
         })
     }
@@ -177,10 +180,12 @@ impl ChannelMessageMode for ChannelMessageModeIngest {
         gen_channel_message_mode_handle!(message, COMMANDS_MODE_INGEST, {
             "PUSH" => ChannelCommandIngest::dispatch_push,
             "POP" => ChannelCommandIngest::dispatch_pop,
+            "REPLACE" => ChannelCommandIngest::dispatch_replace,
             "COUNT" => ChannelCommandIngest::dispatch_count,
             "FLUSHC" => ChannelCommandIngest::dispatch_flushc,
             "FLUSHB" => ChannelCommandIngest::dispatch_flushb,
             "FLUSHO" => ChannelCommandIngest::dispatch_flusho,
+            "LEX" => ChannelCommandBase::dispatch_lex,
             "HELP" => ChannelCommandIngest::dispatch_help,
         })
     }
@@ -8,6 +8,7 @@ use std::ops::Deref;
 use std::sync::RwLock;
 use std::time::Instant;
 
+use crate::query::cache::QueryCache;
 use crate::store::fst::StoreFSTPool;
 use crate::store::kv::StoreKVPool;
 
@@ -29,6 +30,10 @@ pub struct ChannelStatistics {
     pub kv_open_count: usize,
     pub fst_open_count: usize,
     pub fst_consolidate_count: usize,
+    pub query_cache_hits: u64,
+    pub query_cache_misses: u64,
+    pub query_cache_entries: usize,
+    pub query_cache_size: usize,
 }
 
 pub fn ensure_states() {
@@ -44,7 +49,11 @@ pub fn ensure_states() {
 
 impl ChannelStatistics {
     pub fn gather() -> ChannelStatistics {
-        let (kv_count, fst_count) = (StoreKVPool::count(), StoreFSTPool::count());
+        let (kv_count, fst_count, query_cache) = (
+            StoreKVPool::count(),
+            StoreFSTPool::count(),
+            QueryCache::statistics(),
+        );
 
         ChannelStatistics {
             uptime: START_TIME.elapsed().as_secs(),
@@ -55,6 +64,10 @@ impl ChannelStatistics {
             kv_open_count: kv_count,
             fst_open_count: fst_count.0,
             fst_consolidate_count: fst_count.1,
+            query_cache_hits: query_cache.hits,
+            query_cache_misses: query_cache.misses,
+            query_cache_entries: query_cache.entries,
+            query_cache_size: query_cache.size,
         }
     }
 }
@@ -7,6 +7,8 @@
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
+use super::options::ConfigCollectionNormalizeForm;
+
 pub fn server_log_level() -> String {
     "error".to_string()
 }
@@ -31,6 +33,18 @@ pub fn channel_search_query_alternates_try() -> usize {
     4
 }
 
+pub fn channel_search_query_wildcard_expand_maximum() -> usize {
+    100
+}
+
+pub fn channel_search_query_matches_scan_maximum() -> usize {
+    10000
+}
+
+pub fn channel_search_query_cache_size() -> usize {
+    0
+}
+
 pub fn channel_search_suggest_limit_default() -> u16 {
     5
 }
@@ -39,6 +53,14 @@ pub fn channel_search_suggest_limit_maximum() -> u16 {
     20
 }
 
+pub fn channel_search_suggest_rank_candidates() -> usize {
+    100
+}
+
+pub fn channel_search_suggest_context_scan_maximum() -> usize {
+    10000
+}
+
 pub fn channel_search_list_limit_default() -> u16 {
     100
 }
@@ -106,3 +128,55 @@ pub fn store_fst_graph_max_size() -> usize {
 pub fn store_fst_graph_max_words() -> usize {
     250000
 }
+
+pub fn collection_detect_locale_per_sentence() -> bool {
+    false
+}
+
+pub fn collection_stemming() -> bool {
+    false
+}
+
+pub fn collection_normalize_form() -> ConfigCollectionNormalizeForm {
+    ConfigCollectionNormalizeForm::None
+}
+
+pub fn collection_fold_case() -> bool {
+    false
+}
+
+pub fn collection_strip_diacritics() -> bool {
+    false
+}
+
+pub fn collection_normalize_numbers() -> bool {
+    false
+}
+
+pub fn collection_token_pattern_fragments() -> bool {
+    false
+}
+
+pub fn collection_cjk_bigrams() -> bool {
+    false
+}
+
+pub fn collection_transliterate() -> bool {
+    false
+}
+
+pub fn collection_ngrams() -> bool {
+    false
+}
+
+pub fn collection_ngram_size() -> usize {
+    3
+}
+
+pub fn collection_ngram_max_word_length() -> usize {
+    24
+}
+
+pub fn collection_stopwords_disabled() -> bool {
+    false
+}
@@ -4,6 +4,7 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
@@ -15,6 +16,9 @@ pub struct Config {
     pub server: ConfigServer,
     pub channel: ConfigChannel,
     pub store: ConfigStore,
+
+    #[serde(default)]
+    pub collection: HashMap<String, ConfigCollection>,
 }
 
 #[derive(Deserialize)]
@@ -54,12 +58,27 @@ pub struct ConfigChannelSearch {
     #[serde(default = "defaults::channel_search_query_alternates_try")]
     pub query_alternates_try: usize,
 
+    #[serde(default = "defaults::channel_search_query_wildcard_expand_maximum")]
+    pub query_wildcard_expand_maximum: usize,
+
+    #[serde(default = "defaults::channel_search_query_matches_scan_maximum")]
+    pub query_matches_scan_maximum: usize,
+
+    #[serde(default = "defaults::channel_search_query_cache_size")]
+    pub query_cache_size: usize,
+
     #[serde(default = "defaults::channel_search_suggest_limit_default")]
     pub suggest_limit_default: u16,
 
     #[serde(default = "defaults::channel_search_suggest_limit_maximum")]
     pub suggest_limit_maximum: u16,
 
+    #[serde(default = "defaults::channel_search_suggest_rank_candidates")]
+    pub suggest_rank_candidates: usize,
+
+    #[serde(default = "defaults::channel_search_suggest_context_scan_maximum")]
+    pub suggest_context_scan_maximum: usize,
+
     #[serde(default = "defaults::channel_search_list_limit_default")]
     pub list_limit_default: u16,
 
@@ -149,3 +168,90 @@ pub struct ConfigStoreFSTGraph {
     #[serde(default = "defaults::store_fst_graph_max_words")]
     pub max_words: usize,
 }
+
+#[derive(Deserialize)]
+pub struct ConfigCollection {
+    pub synonyms: Option<PathBuf>,
+
+    pub default_locale: Option<String>,
+
+    #[serde(default)]
+    pub allowed_locales: Vec<String>,
+
+    #[serde(default = "defaults::collection_detect_locale_per_sentence")]
+    pub detect_locale_per_sentence: bool,
+
+    #[serde(default = "defaults::collection_stemming")]
+    pub stemming: bool,
+
+    #[serde(default = "defaults::collection_normalize_form")]
+    pub normalize_form: ConfigCollectionNormalizeForm,
+
+    #[serde(default = "defaults::collection_fold_case")]
+    pub fold_case: bool,
+
+    #[serde(default = "defaults::collection_strip_diacritics")]
+    pub strip_diacritics: bool,
+
+    #[serde(default = "defaults::collection_normalize_numbers")]
+    pub normalize_numbers: bool,
+
+    #[serde(default = "defaults::collection_cjk_bigrams")]
+    pub cjk_bigrams: bool,
+
+    #[serde(default = "defaults::collection_transliterate")]
+    pub transliterate: bool,
+
+    #[serde(default = "defaults::collection_ngrams")]
+    pub ngrams: bool,
+
+    #[serde(default = "defaults::collection_ngram_size")]
+    pub ngram_size: usize,
+
+    #[serde(default = "defaults::collection_ngram_max_word_length")]
+    pub ngram_max_word_length: usize,
+
+    #[serde(default)]
+    pub tokenizers: HashMap<String, ConfigCollectionTokenizer>,
+
+    #[serde(default)]
+    pub token_patterns: Vec<String>,
+
+    #[serde(default = "defaults::collection_token_pattern_fragments")]
+    pub token_pattern_fragments: bool,
+
+    #[serde(default)]
+    pub stopwords: HashMap<String, ConfigCollectionStopWords>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigCollectionStopWords {
+    #[serde(default = "defaults::collection_stopwords_disabled")]
+    pub disabled: bool,
+
+    #[serde(default)]
+    pub add: Vec<String>,
+
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigCollectionTokenizer {
+    Uax29,
+    Whitespace,
+    Bigrams,
+    Jieba,
+    Lindera,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigCollectionNormalizeForm {
+    None,
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
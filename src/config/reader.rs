@@ -4,8 +4,10 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use regex::Regex;
 use std::fs::File;
 use std::io::Read;
+use whatlang::Lang;
 
 use super::options::Config;
 use crate::APP_ARGS;
@@ -48,5 +50,47 @@ impl ConfigReader {
         if config.store.fst.graph.consolidate_after >= config.store.fst.pool.inactive_after {
             panic!("consolidate_after for fst must be strictly lower than inactive_after");
         }
+
+        // Check 'stopwords' locales for collections
+        for collection in config.collection.values() {
+            for stopwords_locale in collection.stopwords.keys() {
+                if Lang::from_code(stopwords_locale).is_none() {
+                    panic!("stopwords locale must be a valid iso 639-3 code");
+                }
+            }
+        }
+
+        // Check 'tokenizers' locales for collections
+        for collection in config.collection.values() {
+            for tokenizer_locale in collection.tokenizers.keys() {
+                if tokenizer_locale != "default" && Lang::from_code(tokenizer_locale).is_none() {
+                    panic!("tokenizers locale must be a valid iso 639-3 code or default");
+                }
+            }
+        }
+
+        // Check 'token_patterns' for collections
+        for collection in config.collection.values() {
+            for token_pattern in collection.token_patterns.iter() {
+                if Regex::new(token_pattern).is_err() {
+                    panic!("token_patterns must be valid regular expressions");
+                }
+            }
+        }
+
+        // Check 'default_locale' and 'allowed_locales' for collections
+        for collection in config.collection.values() {
+            if let Some(ref default_locale) = collection.default_locale {
+                if Lang::from_code(default_locale).is_none() {
+                    panic!("default_locale must be a valid iso 639-3 code");
+                }
+            }
+
+            for allowed_locale in collection.allowed_locales.iter() {
+                if Lang::from_code(allowed_locale).is_none() {
+                    panic!("allowed_locales must be valid iso 639-3 codes");
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,167 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hashbrown::HashSet;
+use std::cmp::{self, Reverse};
+
+use crate::lexer::token::TokenLexer;
+use crate::query::types::QuerySearchID;
+use crate::store::fst::{StoreFSTAction, StoreFSTActionBuilder, StoreFSTPool};
+use crate::store::identifiers::{StoreObjectIID, StoreTermHash, StoreTermHashed};
+use crate::store::item::StoreItem;
+use crate::store::kv::{StoreKVAcquireMode, StoreKVAction, StoreKVActionBuilder, StoreKVPool};
+use crate::APP_CONF;
+
+pub struct ExecutorCorrect;
+
+impl ExecutorCorrect {
+    pub fn execute<'a>(
+        store: StoreItem<'a>,
+        _event_id: QuerySearchID,
+        lexer: TokenLexer<'a>,
+    ) -> Result<Option<String>, ()> {
+        if let StoreItem(collection, Some(bucket), None) = store {
+            // Important: acquire database and graph access read locks, and reference them in \
+            //   context. This prevents the database and graph from being erased while using \
+            //   them in this block.
+            general_kv_access_lock_read!();
+            general_fst_access_lock_read!();
+
+            if let (Ok(kv_store), Ok(fst_store)) = (
+                StoreKVPool::acquire(StoreKVAcquireMode::OpenOnly, collection),
+                StoreFSTPool::acquire(collection, bucket),
+            ) {
+                // Important: acquire bucket store read lock
+                executor_kv_lock_read!(kv_store);
+
+                let (kv_action, fst_action) = (
+                    StoreKVActionBuilder::access(bucket, kv_store),
+                    StoreFSTActionBuilder::access(fst_store),
+                );
+
+                // Correct each term with its best candidate, given that the corrected terms \
+                //   found so far still match some objects together (ie. the corrected query \
+                //   is guaranteed to have results)
+                let mut corrected_terms = Vec::new();
+                let mut found_iids: Option<HashSet<StoreObjectIID>> = None;
+
+                for (term, term_hashed) in lexer {
+                    let candidates =
+                        Self::rank_candidates(&kv_action, &fst_action, &term, term_hashed);
+
+                    let picked = candidates
+                        .into_iter()
+                        .find_map(|(candidate, candidate_iids)| {
+                            let candidate_found_iids: HashSet<StoreObjectIID> = match found_iids {
+                                Some(ref found_iids) => candidate_iids
+                                    .into_iter()
+                                    .filter(|iid| found_iids.contains(iid))
+                                    .collect(),
+                                None => candidate_iids.into_iter().collect(),
+                            };
+
+                            if !candidate_found_iids.is_empty() {
+                                Some((candidate, candidate_found_iids))
+                            } else {
+                                None
+                            }
+                        });
+
+                    if let Some((candidate, candidate_found_iids)) = picked {
+                        debug!("corrected term: {} into: {}", term, candidate);
+
+                        corrected_terms.push(candidate);
+                        found_iids = Some(candidate_found_iids);
+                    } else {
+                        debug!("could not find any correction for term: {}", term);
+
+                        return Ok(None);
+                    }
+                }
+
+                return Ok(if !corrected_terms.is_empty() {
+                    Some(corrected_terms.join(" "))
+                } else {
+                    None
+                });
+            }
+        }
+
+        Err(())
+    }
+
+    fn rank_candidates(
+        kv_action: &StoreKVAction,
+        fst_action: &StoreFSTAction,
+        term: &str,
+        term_hashed: StoreTermHashed,
+    ) -> Vec<(String, Vec<StoreObjectIID>)> {
+        let mut candidates = Vec::new();
+
+        // Term is indexed as-is? It comes first, as it does not need any correction
+        if let Some(term_iids) = kv_action.get_term_to_iids(term_hashed).unwrap_or(None) {
+            candidates.push((0, term.to_owned(), term_iids));
+        }
+
+        // Rank typo corrections by edit distance, then by popularity (ie. the number of \
+        //   objects they appear in)
+        if let Some(corrected_words) =
+            fst_action.correct_words(term, APP_CONF.channel.search.suggest_rank_candidates)
+        {
+            for corrected_word in corrected_words {
+                if corrected_word != term {
+                    if let Some(corrected_iids) = kv_action
+                        .get_term_to_iids(StoreTermHash::from(&corrected_word))
+                        .unwrap_or(None)
+                    {
+                        candidates.push((
+                            Self::edit_distance(term, &corrected_word),
+                            corrected_word,
+                            corrected_iids,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Notice: sort is stable, so that the term itself always stays first
+        candidates.sort_by_key(|candidate| (candidate.0, Reverse(candidate.2.len())));
+
+        candidates
+            .into_iter()
+            .map(|(_, candidate, candidate_iids)| (candidate, candidate_iids))
+            .collect()
+    }
+
+    fn edit_distance(from_word: &str, to_word: &str) -> usize {
+        // Compute the Levenshtein distance between both words (on characters, not on bytes)
+        let to_chars = to_word.chars().collect::<Vec<char>>();
+        let mut distances = (0..=to_chars.len()).collect::<Vec<usize>>();
+
+        for (from_index, from_char) in from_word.chars().enumerate() {
+            let mut previous_diagonal = distances[0];
+
+            distances[0] = from_index + 1;
+
+            for (to_index, to_char) in to_chars.iter().enumerate() {
+                let previous_above = distances[to_index + 1];
+
+                distances[to_index + 1] = if from_char == *to_char {
+                    previous_diagonal
+                } else {
+                    1 + cmp::min(
+                        previous_diagonal,
+                        cmp::min(previous_above, distances[to_index]),
+                    )
+                };
+
+                previous_diagonal = previous_above;
+            }
+        }
+
+        distances[to_chars.len()]
+    }
+}
@@ -35,7 +35,7 @@ impl ExecutorCount {
                         .get_oid_to_iid(oid)
                         .unwrap_or(None)
                         .map(|iid| {
-                            // List terms for IID
+                            // List terms for IID (n-grams are stored separately, thus not counted)
                             if let Some(terms) = kv_action.get_iid_to_terms(iid).unwrap_or(None) {
                                 terms.len() as u32
                             } else {
@@ -4,7 +4,7 @@
 // Copyright: 2022, Troy Kohler <troy.kohler@zalando.de>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use crate::query::types::{QuerySearchID, QuerySearchLimit, QuerySearchOffset};
+use crate::query::types::{QueryListOptions, QuerySearchCursor, QuerySearchID};
 use crate::store::fst::StoreFSTActionBuilder;
 use crate::store::fst::StoreFSTPool;
 use crate::store::item::StoreItem;
@@ -15,8 +15,7 @@ impl ExecutorList {
     pub fn execute(
         store: StoreItem,
         _event_id: QuerySearchID,
-        limit: QuerySearchLimit,
-        offset: QuerySearchOffset,
+        options: QueryListOptions,
     ) -> Result<Vec<String>, ()> {
         if let StoreItem(collection, Some(bucket), None) = store {
             // Important: acquire graph access read lock, and reference it in context. This \
@@ -28,7 +27,43 @@ impl ExecutorList {
 
                 debug!("running list");
 
-                return fst_action.list_words(limit as usize, offset as usize);
+                let limit = options.limit as usize;
+
+                // Notice: list one more word than requested if a cursor is to be returned, as \
+                //   to know whether there are more words to list after this page.
+                let mut words = fst_action.list_words(
+                    if options.with_cursor {
+                        limit + 1
+                    } else {
+                        limit
+                    },
+                    options.offset as usize,
+                    options.prefix.as_deref(),
+                    options.pattern.as_deref(),
+                    options.cursor.as_ref().map(|cursor| cursor.as_bytes()),
+                )?;
+
+                // Prepend cursor to resume listing from? (format: 'cursor(<cursor>)'; it always \
+                //   comes first so that it can never be mistaken for a word, and it is left \
+                //   empty if there are no more words after this page)
+                if options.with_cursor {
+                    let cursor = if words.len() > limit {
+                        words.truncate(limit);
+
+                        words
+                            .last()
+                            .map(|last_word| {
+                                QuerySearchCursor::from_bytes(last_word.as_bytes()).to_value()
+                            })
+                            .unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+
+                    words.insert(0, format!("cursor({})", cursor));
+                }
+
+                return Ok(words);
             }
         }
 
@@ -7,6 +7,7 @@
 #[macro_use]
 mod macros;
 
+pub mod correct;
 pub mod count;
 pub mod flushb;
 pub mod flushc;
@@ -14,5 +15,6 @@ pub mod flusho;
 pub mod list;
 pub mod pop;
 pub mod push;
+pub mod replace;
 pub mod search;
 pub mod suggest;
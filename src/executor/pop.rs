@@ -7,6 +7,7 @@
 use linked_hash_set::LinkedHashSet;
 use std::iter::FromIterator;
 
+use crate::lexer::ngrams::LexerNGrams;
 use crate::lexer::token::TokenLexer;
 use crate::store::fst::{StoreFSTActionBuilder, StoreFSTPool};
 use crate::store::identifiers::StoreTermHashed;
@@ -84,6 +85,17 @@ impl ExecutorPop {
                                 } else {
                                     info!("nuke only certain terms for pop executor");
 
+                                    // Acquire n-grams splitter (if enabled for collection), \
+                                    //   along with list of n-grams for IID
+                                    let ngrams = LexerNGrams::from(collection.as_str());
+
+                                    let mut iid_ngrams_hashed: Vec<StoreTermHashed> = kv_action
+                                        .get_iid_to_ngrams(iid)
+                                        .unwrap_or(None)
+                                        .unwrap_or_default();
+
+                                    let mut has_ngram_commits = false;
+
                                     // Nuke IID in Term-to-IIDs list
                                     for (pop_term, pop_term_hashed) in &pop_terms {
                                         // Check that term is linked to IID (and should be removed)
@@ -119,6 +131,35 @@ impl ExecutorPop {
                                                     "failed getting term-to-iids in pop executor"
                                                 );
                                             }
+
+                                            // Unlink character n-grams of term from IID, if \
+                                            //   no remaining term holds them (an n-gram is \
+                                            //   listed once per term that holds it)
+                                            if let Some(ngram_hashes) = ngrams
+                                                .as_ref()
+                                                .and_then(|ngrams| ngrams.hashes(pop_term))
+                                            {
+                                                for ngram_hashed in ngram_hashes {
+                                                    if let Some(ngram_index) = iid_ngrams_hashed
+                                                        .iter()
+                                                        .position(|cur| cur == &ngram_hashed)
+                                                    {
+                                                        has_ngram_commits = true;
+
+                                                        iid_ngrams_hashed.remove(ngram_index);
+
+                                                        if !iid_ngrams_hashed
+                                                            .contains(&ngram_hashed)
+                                                        {
+                                                            executor_ensure_op!(kv_action
+                                                                .unlink_term_iid(
+                                                                    ngram_hashed,
+                                                                    iid
+                                                                ));
+                                                        }
+                                                    }
+                                                }
+                                            }
                                         }
                                     }
 
@@ -129,6 +170,16 @@ impl ExecutorPop {
                                     executor_ensure_op!(
                                         kv_action.set_iid_to_terms(iid, &remaining_terms_vec)
                                     );
+
+                                    // Bump IID-to-NGrams list? (if any n-gram was unlinked)
+                                    if has_ngram_commits {
+                                        if iid_ngrams_hashed.is_empty() {
+                                            executor_ensure_op!(kv_action.delete_iid_to_ngrams(iid));
+                                        } else {
+                                            executor_ensure_op!(kv_action
+                                                .set_iid_to_ngrams(iid, &iid_ngrams_hashed));
+                                        }
+                                    }
                                 }
                             }
                         } else {
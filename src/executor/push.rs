@@ -4,20 +4,30 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use hashbrown::HashSet;
 use linked_hash_set::LinkedHashSet;
 use std::iter::FromIterator;
 
+use crate::lexer::ngrams::LexerNGrams;
 use crate::lexer::token::TokenLexer;
+use crate::query::types::QueryPushSort;
 use crate::store::fst::{StoreFSTActionBuilder, StoreFSTPool};
-use crate::store::identifiers::{StoreMetaKey, StoreMetaValue, StoreTermHashed};
+use crate::store::identifiers::{
+    StoreMetaKey, StoreMetaValue, StoreObjectIID, StoreObjectOID, StoreSortKeyHash,
+    StoreSortKeyHashed, StoreSortValue, StoreTermHashed,
+};
 use crate::store::item::StoreItem;
-use crate::store::kv::{StoreKVAcquireMode, StoreKVActionBuilder, StoreKVPool};
+use crate::store::kv::{StoreKVAcquireMode, StoreKVAction, StoreKVActionBuilder, StoreKVPool};
 use crate::APP_CONF;
 
 pub struct ExecutorPush;
 
 impl ExecutorPush {
-    pub fn execute<'a>(store: StoreItem<'a>, lexer: TokenLexer<'a>) -> Result<(), ()> {
+    pub fn execute<'a>(
+        store: StoreItem<'a>,
+        lexer: TokenLexer<'a>,
+        sorts: Vec<QueryPushSort>,
+    ) -> Result<(), ()> {
         if let StoreItem(collection, Some(bucket), Some(object)) = store {
             // Important: acquire database access read lock, and reference it in context. This \
             //   prevents the database from being erased while using it in this block.
@@ -36,49 +46,16 @@ impl ExecutorPush {
                     StoreFSTActionBuilder::access(fst_store),
                 );
 
-                // Try to resolve existing OID to IID, otherwise initialize IID (store the \
-                //   bi-directional relationship)
+                // Acquire IID for OID (it gets initialized if the object is new)
                 let oid = object.as_str();
-                let iid = kv_action.get_oid_to_iid(oid).unwrap_or(None).or_else(|| {
-                    info!("must initialize push executor oid-to-iid and iid-to-oid");
-
-                    if let Ok(iid_incr) = kv_action.get_meta_to_value(StoreMetaKey::IIDIncr) {
-                        let iid_incr = if let Some(iid_incr) = iid_incr {
-                            match iid_incr {
-                                StoreMetaValue::IIDIncr(iid_incr) => iid_incr + 1,
-                            }
-                        } else {
-                            0
-                        };
-
-                        // Bump last stored increment
-                        if kv_action
-                            .set_meta_to_value(
-                                StoreMetaKey::IIDIncr,
-                                StoreMetaValue::IIDIncr(iid_incr),
-                            )
-                            .is_ok()
-                        {
-                            // Associate OID <> IID (bidirectional)
-                            executor_ensure_op!(kv_action.set_oid_to_iid(oid, iid_incr));
-                            executor_ensure_op!(kv_action.set_iid_to_oid(iid_incr, oid));
-
-                            Some(iid_incr)
-                        } else {
-                            error!("failed updating push executor meta-to-value iid increment");
-
-                            None
-                        }
-                    } else {
-                        error!("failed getting push executor meta-to-value iid increment");
-
-                        None
-                    }
-                });
+                let iid = Self::acquire_iid(&kv_action, oid);
 
                 if let Some(iid) = iid {
                     let mut has_commits = false;
 
+                    // Acquire n-grams splitter (if enabled for collection)
+                    let ngrams = LexerNGrams::from(collection.as_str());
+
                     // Acquire list of terms for IID
                     let mut iid_terms_hashed: LinkedHashSet<StoreTermHashed> =
                         LinkedHashSet::from_iter(
@@ -93,49 +70,45 @@ impl ExecutorPush {
                         iid_terms_hashed
                     );
 
-                    for (term, term_hashed) in lexer {
-                        // Check that term is not already linked to IID
-                        if !iid_terms_hashed.contains(&term_hashed) {
-                            if let Ok(term_iids) = kv_action.get_term_to_iids(term_hashed) {
-                                has_commits = true;
-
-                                // Add IID in first position in list for terms
-                                let mut term_iids = term_iids.unwrap_or_default();
-
-                                // Remove IID from list of IIDs to be popped before inserting in \
-                                //   first position?
-                                if term_iids.contains(&iid) {
-                                    term_iids.retain(|cur_iid| cur_iid != &iid);
-                                }
+                    // Acquire list of n-grams for IID (an n-gram is listed once per term that \
+                    //   holds it, so that it can be unlinked once no term holds it anymore)
+                    let mut iid_ngrams_hashed: Vec<StoreTermHashed> = kv_action
+                        .get_iid_to_ngrams(iid)
+                        .unwrap_or(None)
+                        .unwrap_or_default();
 
-                                info!("has push executor term-to-iids: {}", iid);
+                    let mut iid_ngrams_linked: HashSet<StoreTermHashed> =
+                        iid_ngrams_hashed.iter().copied().collect();
 
-                                term_iids.insert(0, iid);
+                    let mut has_ngram_commits = false;
 
-                                // Truncate IIDs linked to term? (ie. storage is too long)
-                                let truncate_limit = APP_CONF.store.kv.retain_word_objects;
-
-                                if term_iids.len() > truncate_limit {
-                                    info!(
-                                        "push executor term-to-iids object too long (limit: {})",
-                                        truncate_limit
-                                    );
-
-                                    // Drain overflowing IIDs (ie. oldest ones that overflow)
-                                    let term_iids_drain = term_iids.drain(truncate_limit..);
-
-                                    executor_ensure_op!(kv_action
-                                        .batch_truncate_object(term_hashed, term_iids_drain));
+                    for (term, term_hashed) in lexer {
+                        // Check that term is not already linked to IID
+                        if !iid_terms_hashed.contains(&term_hashed)
+                            && Self::link_term_iid(&kv_action, iid, term_hashed)
+                        {
+                            has_commits = true;
+
+                            // Insert term into IID to terms map
+                            iid_terms_hashed.insert(term_hashed);
+
+                            // Link term character n-grams to IID? (if enabled for collection; \
+                            //   n-grams are used for substring matching)
+                            if let Some(ngram_hashes) =
+                                ngrams.as_ref().and_then(|ngrams| ngrams.hashes(&term))
+                            {
+                                for ngram_hashed in ngram_hashes {
+                                    // List n-gram for IID only if it is linked to IID (as \
+                                    //   n-grams get unlinked by counting their occurrences)
+                                    if iid_ngrams_linked.contains(&ngram_hashed)
+                                        || Self::link_term_iid(&kv_action, iid, ngram_hashed)
+                                    {
+                                        has_ngram_commits = true;
+
+                                        iid_ngrams_linked.insert(ngram_hashed);
+                                        iid_ngrams_hashed.push(ngram_hashed);
+                                    }
                                 }
-
-                                executor_ensure_op!(
-                                    kv_action.set_term_to_iids(term_hashed, &term_iids)
-                                );
-
-                                // Insert term into IID to terms map
-                                iid_terms_hashed.insert(term_hashed);
-                            } else {
-                                error!("failed getting push executor term-to-iids");
                             }
                         }
 
@@ -158,6 +131,14 @@ impl ExecutorPush {
                         executor_ensure_op!(kv_action.set_iid_to_terms(iid, &collected_iids));
                     }
 
+                    // Commit updated list of n-grams for IID? (if any commit made)
+                    if has_ngram_commits {
+                        executor_ensure_op!(kv_action.set_iid_to_ngrams(iid, &iid_ngrams_hashed));
+                    }
+
+                    // Merge provided sort values with stored sort values for IID
+                    Self::merge_sorts(&kv_action, iid, sorts);
+
                     return Ok(());
                 }
             }
@@ -165,4 +146,110 @@ impl ExecutorPush {
 
         Err(())
     }
+
+    pub fn link_term_iid(
+        kv_action: &StoreKVAction,
+        iid: StoreObjectIID,
+        term_hashed: StoreTermHashed,
+    ) -> bool {
+        if let Ok(term_iids) = kv_action.get_term_to_iids(term_hashed) {
+            // Add IID in first position in list for terms
+            let mut term_iids = term_iids.unwrap_or_default();
+
+            // Remove IID from list of IIDs to be popped before inserting in first position?
+            if term_iids.contains(&iid) {
+                term_iids.retain(|cur_iid| cur_iid != &iid);
+            }
+
+            info!("has push executor term-to-iids: {}", iid);
+
+            term_iids.insert(0, iid);
+
+            // Truncate IIDs linked to term? (ie. storage is too long)
+            let truncate_limit = APP_CONF.store.kv.retain_word_objects;
+
+            if term_iids.len() > truncate_limit {
+                info!(
+                    "push executor term-to-iids object too long (limit: {})",
+                    truncate_limit
+                );
+
+                // Drain overflowing IIDs (ie. oldest ones that overflow)
+                let term_iids_drain = term_iids.drain(truncate_limit..);
+
+                executor_ensure_op!(kv_action.batch_truncate_object(term_hashed, term_iids_drain));
+            }
+
+            executor_ensure_op!(kv_action.set_term_to_iids(term_hashed, &term_iids));
+
+            true
+        } else {
+            error!("failed getting push executor term-to-iids");
+
+            false
+        }
+    }
+
+    pub fn acquire_iid<'a>(
+        kv_action: &StoreKVAction<'a>,
+        oid: StoreObjectOID<'a>,
+    ) -> Option<StoreObjectIID> {
+        // Try to resolve existing OID to IID, otherwise initialize IID (store the \
+        //   bi-directional relationship)
+        kv_action.get_oid_to_iid(oid).unwrap_or(None).or_else(|| {
+            info!("must initialize push executor oid-to-iid and iid-to-oid");
+
+            if let Ok(iid_incr) = kv_action.get_meta_to_value(StoreMetaKey::IIDIncr) {
+                let iid_incr = if let Some(iid_incr) = iid_incr {
+                    match iid_incr {
+                        StoreMetaValue::IIDIncr(iid_incr) => iid_incr + 1,
+                    }
+                } else {
+                    0
+                };
+
+                // Bump last stored increment
+                if kv_action
+                    .set_meta_to_value(StoreMetaKey::IIDIncr, StoreMetaValue::IIDIncr(iid_incr))
+                    .is_ok()
+                {
+                    // Associate OID <> IID (bidirectional)
+                    executor_ensure_op!(kv_action.set_oid_to_iid(oid, iid_incr));
+                    executor_ensure_op!(kv_action.set_iid_to_oid(iid_incr, oid));
+
+                    Some(iid_incr)
+                } else {
+                    error!("failed updating push executor meta-to-value iid increment");
+
+                    None
+                }
+            } else {
+                error!("failed getting push executor meta-to-value iid increment");
+
+                None
+            }
+        })
+    }
+
+    pub fn merge_sorts(kv_action: &StoreKVAction, iid: StoreObjectIID, sorts: Vec<QueryPushSort>) {
+        // Merge provided sort values with stored sort values for IID? (a sort value that was \
+        //   previously stored for the same key gets replaced)
+        if !sorts.is_empty() {
+            let mut iid_sorts: Vec<(StoreSortKeyHashed, StoreSortValue)> = kv_action
+                .get_iid_to_sorts(iid)
+                .unwrap_or(None)
+                .unwrap_or_default();
+
+            for sort in sorts {
+                let sort_key_hashed = StoreSortKeyHash::from(&sort.key);
+
+                iid_sorts.retain(|cur_sort| cur_sort.0 != sort_key_hashed);
+                iid_sorts.push((sort_key_hashed, sort.value));
+            }
+
+            info!("has push executor iid-to-sorts commits: {:?}", iid_sorts);
+
+            executor_ensure_op!(kv_action.set_iid_to_sorts(iid, &iid_sorts));
+        }
+    }
 }
@@ -0,0 +1,302 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hashbrown::{HashMap, HashSet};
+use linked_hash_set::LinkedHashSet;
+use std::iter::FromIterator;
+
+use super::push::ExecutorPush;
+use crate::lexer::ngrams::LexerNGrams;
+use crate::lexer::token::TokenLexer;
+use crate::query::types::QueryPushSort;
+use crate::store::fst::{StoreFSTActionBuilder, StoreFSTPool};
+use crate::store::identifiers::{StoreObjectIID, StoreTermHashed};
+use crate::store::item::StoreItem;
+use crate::store::kv::{StoreKVAcquireMode, StoreKVAction, StoreKVActionBuilder, StoreKVPool};
+use crate::APP_CONF;
+
+pub struct ExecutorReplace;
+
+impl ExecutorReplace {
+    pub fn execute<'a>(
+        store: StoreItem<'a>,
+        lexer: TokenLexer<'a>,
+        sorts: Vec<QueryPushSort>,
+    ) -> Result<(), ()> {
+        if let StoreItem(collection, Some(bucket), Some(object)) = store {
+            // Important: acquire database access read lock, and reference it in context. This \
+            //   prevents the database from being erased while using it in this block.
+            general_kv_access_lock_read!();
+            general_fst_access_lock_read!();
+
+            if let (Ok(kv_store), Ok(fst_store)) = (
+                StoreKVPool::acquire(StoreKVAcquireMode::Any, collection),
+                StoreFSTPool::acquire(collection, bucket),
+            ) {
+                // Important: acquire bucket store write lock
+                executor_kv_lock_write!(kv_store);
+
+                let (kv_action, fst_action) = (
+                    StoreKVActionBuilder::access(bucket, kv_store),
+                    StoreFSTActionBuilder::access(fst_store),
+                );
+
+                // Stage all writes in a single write batch, so that the object gets replaced \
+                //   at once (ie. it never disappears from search, even if Sonic crashes)
+                kv_action.begin_write_batch();
+
+                // Acquire IID for OID (it gets initialized if the object is new)
+                let oid = object.as_str();
+
+                if let Some(iid) = ExecutorPush::acquire_iid(&kv_action, oid) {
+                    // Acquire n-grams splitter (if enabled for collection)
+                    let ngrams = LexerNGrams::from(collection.as_str());
+
+                    // Collect replacement terms, along with their character n-grams (if enabled \
+                    //   for collection; n-grams are used for substring matching, and are listed \
+                    //   once per term that holds them)
+                    let mut terms = Vec::new();
+                    let mut terms_hashed: LinkedHashSet<StoreTermHashed> = LinkedHashSet::new();
+                    let mut ngrams_hashed: Vec<StoreTermHashed> = Vec::new();
+
+                    for (term, term_hashed) in lexer {
+                        if terms_hashed.insert(term_hashed) {
+                            if let Some(ngram_hashes) =
+                                ngrams.as_ref().and_then(|ngrams| ngrams.hashes(&term))
+                            {
+                                ngrams_hashed.extend(ngram_hashes);
+                            }
+                        }
+
+                        terms.push(term);
+                    }
+
+                    let ngrams_hashed_unique: LinkedHashSet<StoreTermHashed> =
+                        LinkedHashSet::from_iter(ngrams_hashed.iter().copied());
+
+                    // Acquire lists of terms and n-grams for IID
+                    let iid_terms_hashed: LinkedHashSet<StoreTermHashed> = LinkedHashSet::from_iter(
+                        kv_action
+                            .get_iid_to_terms(iid)
+                            .unwrap_or(None)
+                            .unwrap_or_default(),
+                    );
+                    let iid_ngrams_hashed: LinkedHashSet<StoreTermHashed> =
+                        LinkedHashSet::from_iter(
+                            kv_action
+                                .get_iid_to_ngrams(iid)
+                                .unwrap_or(None)
+                                .unwrap_or_default(),
+                        );
+
+                    info!(
+                        "got replace executor stored iid-to-terms: {:?}",
+                        iid_terms_hashed
+                    );
+
+                    // Stage updated lists of IIDs for terms in memory first, as a list may be \
+                    //   updated several times, while reads do not see writes staged in the \
+                    //   write batch
+                    let mut terms_iids: HashMap<StoreTermHashed, Vec<StoreObjectIID>> =
+                        HashMap::new();
+
+                    // Unlink stale terms and n-grams from IID (ie. those that are not in \
+                    //   replacement terms)
+                    for stale_term_hashed in iid_terms_hashed
+                        .difference(&terms_hashed)
+                        .chain(iid_ngrams_hashed.difference(&ngrams_hashed_unique))
+                    {
+                        Self::stage_term_iids(&kv_action, &mut terms_iids, *stale_term_hashed)
+                            .retain(|cur_iid| cur_iid != &iid);
+                    }
+
+                    // Link new terms and n-grams to IID (ie. those that are not already linked)
+                    let mut truncated_iids: HashMap<StoreObjectIID, Vec<StoreTermHashed>> =
+                        HashMap::new();
+
+                    let truncate_limit = APP_CONF.store.kv.retain_word_objects;
+
+                    for new_term_hashed in terms_hashed
+                        .difference(&iid_terms_hashed)
+                        .chain(ngrams_hashed_unique.difference(&iid_ngrams_hashed))
+                    {
+                        let term_iids =
+                            Self::stage_term_iids(&kv_action, &mut terms_iids, *new_term_hashed);
+
+                        // Add IID in first position in list for terms
+                        term_iids.retain(|cur_iid| cur_iid != &iid);
+                        term_iids.insert(0, iid);
+
+                        // Truncate IIDs linked to term? (ie. storage is too long)
+                        if term_iids.len() > truncate_limit {
+                            info!(
+                                "replace executor term-to-iids object too long (limit: {})",
+                                truncate_limit
+                            );
+
+                            for truncated_iid in term_iids.drain(truncate_limit..) {
+                                truncated_iids
+                                    .entry(truncated_iid)
+                                    .or_default()
+                                    .push(*new_term_hashed);
+                            }
+                        }
+                    }
+
+                    // Remove links to truncated terms from overflowing IIDs (their keys are \
+                    //   distinct from IID keys, so they get staged in the same write batch)
+                    for (truncated_iid, truncated_terms_hashed) in truncated_iids {
+                        Self::truncate_object(
+                            &kv_action,
+                            &mut terms_iids,
+                            truncated_iid,
+                            &truncated_terms_hashed,
+                        );
+                    }
+
+                    // Collect stale words that are not linked to any object anymore (they get \
+                    //   popped from the graph, once resolved from their hashes)
+                    let orphan_terms_hashed: HashSet<StoreTermHashed> = iid_terms_hashed
+                        .difference(&terms_hashed)
+                        .filter(|stale_term_hashed| {
+                            terms_iids
+                                .get(*stale_term_hashed)
+                                .map(|term_iids| term_iids.is_empty())
+                                .unwrap_or(false)
+                        })
+                        .copied()
+                        .collect();
+
+                    // Stage updated lists of IIDs for terms
+                    for (term_hashed, term_iids) in terms_iids {
+                        if term_iids.is_empty() {
+                            executor_ensure_op!(kv_action.delete_term_to_iids(term_hashed));
+                        } else {
+                            executor_ensure_op!(kv_action.set_term_to_iids(term_hashed, &term_iids));
+                        }
+                    }
+
+                    if terms_hashed.is_empty() {
+                        info!("replace executor has no terms, flushing object");
+
+                        // No term left, thus flush object (terms were already unlinked from IID)
+                        executor_ensure_op!(kv_action.delete_oid_to_iid(oid));
+                        executor_ensure_op!(kv_action.delete_iid_to_oid(iid));
+                        executor_ensure_op!(kv_action.delete_iid_to_terms(iid));
+                        executor_ensure_op!(kv_action.delete_iid_to_ngrams(iid));
+                        executor_ensure_op!(kv_action.delete_iid_to_sorts(iid));
+                    } else {
+                        let collected_iids: Vec<StoreTermHashed> =
+                            terms_hashed.into_iter().collect();
+
+                        info!(
+                            "has replace executor iid-to-terms commits: {:?}",
+                            collected_iids
+                        );
+
+                        executor_ensure_op!(kv_action.set_iid_to_terms(iid, &collected_iids));
+
+                        if ngrams_hashed.is_empty() {
+                            executor_ensure_op!(kv_action.delete_iid_to_ngrams(iid));
+                        } else {
+                            executor_ensure_op!(kv_action.set_iid_to_ngrams(iid, &ngrams_hashed));
+                        }
+
+                        // Merge provided sort values with stored sort values for IID
+                        ExecutorPush::merge_sorts(&kv_action, iid, sorts);
+                    }
+
+                    // Commit all staged writes at once
+                    if kv_action.commit_write_batch().is_err() {
+                        error!("failed committing replace executor write batch");
+
+                        return Err(());
+                    }
+
+                    // Push to FST graph
+                    for term in terms {
+                        if fst_action.push_word(&term) {
+                            debug!("replace term committed to graph: {}", term);
+                        }
+                    }
+
+                    // Pop stale words that are not linked to any object anymore from FST graph
+                    if !orphan_terms_hashed.is_empty() {
+                        for orphan_term in fst_action.resolve_words(&orphan_terms_hashed) {
+                            if fst_action.pop_word(&orphan_term) {
+                                debug!("replace term nuked from graph: {}", orphan_term);
+                            }
+                        }
+                    }
+
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(())
+    }
+
+    fn stage_term_iids<'b>(
+        kv_action: &StoreKVAction,
+        terms_iids: &'b mut HashMap<StoreTermHashed, Vec<StoreObjectIID>>,
+        term_hashed: StoreTermHashed,
+    ) -> &'b mut Vec<StoreObjectIID> {
+        terms_iids.entry(term_hashed).or_insert_with(|| {
+            kv_action
+                .get_term_to_iids(term_hashed)
+                .unwrap_or(None)
+                .unwrap_or_default()
+        })
+    }
+
+    fn truncate_object(
+        kv_action: &StoreKVAction,
+        terms_iids: &mut HashMap<StoreTermHashed, Vec<StoreObjectIID>>,
+        iid: StoreObjectIID,
+        truncated_terms_hashed: &[StoreTermHashed],
+    ) {
+        debug!("replace executor truncate object iid: {}", iid);
+
+        if let Ok(Some(mut iid_terms_hashed)) = kv_action.get_iid_to_terms(iid) {
+            let mut iid_ngrams_hashed = kv_action
+                .get_iid_to_ngrams(iid)
+                .unwrap_or(None)
+                .unwrap_or_default();
+
+            // Nuke truncated terms and n-grams in lists for IID
+            iid_terms_hashed.retain(|cur_term| !truncated_terms_hashed.contains(cur_term));
+            iid_ngrams_hashed.retain(|cur_ngram| !truncated_terms_hashed.contains(cur_ngram));
+
+            if iid_terms_hashed.is_empty() {
+                // No term left, thus flush whole object (unlink its remaining n-grams)
+                for iid_ngram_hashed in iid_ngrams_hashed {
+                    Self::stage_term_iids(kv_action, terms_iids, iid_ngram_hashed)
+                        .retain(|cur_iid| cur_iid != &iid);
+                }
+
+                if let Ok(Some(oid)) = kv_action.get_iid_to_oid(iid) {
+                    executor_ensure_op!(kv_action.delete_oid_to_iid(&oid));
+                } else {
+                    error!("failed getting replace executor truncate object iid-to-oid");
+                }
+
+                executor_ensure_op!(kv_action.delete_iid_to_oid(iid));
+                executor_ensure_op!(kv_action.delete_iid_to_terms(iid));
+                executor_ensure_op!(kv_action.delete_iid_to_ngrams(iid));
+                executor_ensure_op!(kv_action.delete_iid_to_sorts(iid));
+            } else {
+                executor_ensure_op!(kv_action.set_iid_to_terms(iid, &iid_terms_hashed));
+
+                if iid_ngrams_hashed.is_empty() {
+                    executor_ensure_op!(kv_action.delete_iid_to_ngrams(iid));
+                } else {
+                    executor_ensure_op!(kv_action.set_iid_to_ngrams(iid, &iid_ngrams_hashed));
+                }
+            }
+        }
+    }
+}
@@ -4,26 +4,96 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use hashbrown::{HashMap, HashSet};
 use linked_hash_set::LinkedHashSet;
+use std::cmp::Ordering;
+use std::fmt;
 use std::iter::FromIterator;
 
-use crate::lexer::token::TokenLexer;
-use crate::query::types::{QuerySearchID, QuerySearchLimit, QuerySearchOffset};
-use crate::store::fst::{StoreFSTActionBuilder, StoreFSTPool};
-use crate::store::identifiers::{StoreObjectIID, StoreTermHash};
+use crate::lexer::ngrams::LexerNGrams;
+use crate::lexer::synonyms::LexerSynonyms;
+use crate::lexer::token::{TokenLexer, TokenLexerBuilder, TokenLexerMode};
+use crate::query::cache::{QueryCache, QueryCacheKey};
+use crate::query::types::{
+    QuerySearchCursor, QuerySearchID, QuerySearchOptions, QuerySearchOrder,
+    QuerySearchOrderDirection, QuerySearchRange,
+};
+use crate::store::fst::{StoreFSTAction, StoreFSTActionBuilder, StoreFSTPool};
+use crate::store::identifiers::{
+    StoreObjectIID, StoreSortKeyHash, StoreSortKeyHashed, StoreSortValue, StoreTermHash,
+    StoreTermHashed,
+};
 use crate::store::item::StoreItem;
-use crate::store::kv::{StoreKVAcquireMode, StoreKVActionBuilder, StoreKVPool};
+use crate::store::kv::{StoreKVAcquireMode, StoreKVAction, StoreKVActionBuilder, StoreKVPool};
 use crate::APP_CONF;
 
 pub struct ExecutorSearch;
 
+#[derive(Default)]
+pub struct ExecutorSearchTrace {
+    parts: Vec<String>,
+}
+
 impl ExecutorSearch {
     pub fn execute<'a>(
         store: StoreItem<'a>,
         _event_id: QuerySearchID,
+        mut lexer: TokenLexer<'a>,
+        options: QuerySearchOptions,
+    ) -> Result<Option<Vec<String>>, ()> {
+        // Notice: terms are lexed beforehand, as they are part of the query cache key
+        let terms = lexer.by_ref().collect::<Vec<(String, StoreTermHashed)>>();
+
+        // Serve results from the query cache? (if enabled)
+        if let (true, StoreItem(collection, Some(bucket), None)) =
+            (QueryCache::is_enabled(), &store)
+        {
+            let cache_key = QueryCacheKey::from(
+                collection.as_str(),
+                bucket.as_str(),
+                lexer.locale().map(|locale| locale.code()),
+                terms.iter().map(|(term, _)| term.to_owned()).collect(),
+                options.clone(),
+            );
+
+            match QueryCache::get(&cache_key) {
+                Ok(cached_results) => Ok(cached_results),
+                Err(cache_generation) => {
+                    let results = Self::search(store, lexer, terms, options, None)?;
+
+                    QueryCache::put(cache_key, cache_generation, &results);
+
+                    Ok(results)
+                }
+            }
+        } else {
+            Self::search(store, lexer, terms, options, None)
+        }
+    }
+
+    pub fn explain<'a>(
+        store: StoreItem<'a>,
+        _event_id: QuerySearchID,
+        mut lexer: TokenLexer<'a>,
+        options: QuerySearchOptions,
+    ) -> Result<Option<String>, ()> {
+        let mut trace = ExecutorSearchTrace::default();
+
+        // Keep track of stop-words dropped by the lexer
+        lexer.trace_dropped();
+
+        let terms = lexer.by_ref().collect::<Vec<(String, StoreTermHashed)>>();
+
+        Self::search(store, lexer, terms, options, Some(&mut trace))
+            .map(|_| Some(trace.to_string()))
+    }
+
+    fn search<'a>(
+        store: StoreItem<'a>,
         lexer: TokenLexer<'a>,
-        limit: QuerySearchLimit,
-        offset: QuerySearchOffset,
+        terms: Vec<(String, StoreTermHashed)>,
+        options: QuerySearchOptions,
+        mut trace: Option<&mut ExecutorSearchTrace>,
     ) -> Result<Option<Vec<String>>, ()> {
         if let StoreItem(collection, Some(bucket), None) = store {
             // Important: acquire database access read lock, and reference it in context. This \
@@ -47,7 +117,29 @@ impl ExecutorSearch {
                 //   all resulting IIDs for each given term.
                 let mut found_iids: LinkedHashSet<StoreObjectIID> = LinkedHashSet::new();
 
-                'lexing: for (term, term_hashed) in lexer {
+                // Keep track of indexed words that matched each term, per IID (only if requested)
+                let mut found_matches: HashMap<StoreObjectIID, Vec<String>> = HashMap::new();
+
+                // Acquire n-grams splitter for substring matching (if enabled for collection)
+                let ngrams = LexerNGrams::from(collection.as_str());
+
+                // Acquire the lexer mode for synonyms (as synonyms must be lexed the same way)
+                let synonym_mode = lexer.resolved_mode();
+
+                if let Some(trace) = trace.as_mut() {
+                    trace.push(
+                        "locale",
+                        lexer.locale().map(|locale| locale.code()).unwrap_or("none"),
+                    );
+                    trace.push("method", lexer.locale_method().as_str());
+                }
+
+                // Number of terms that were evaluated (others are skipped if no IID is left)
+                let mut terms_evaluated = terms.len();
+
+                'lexing: for (index, (term, term_hashed)) in terms.iter().enumerate() {
+                    let (term, term_hashed) = (term.as_str(), *term_hashed);
+
                     let mut iids = LinkedHashSet::from_iter(
                         kv_action
                             .get_term_to_iids(term_hashed)
@@ -56,6 +148,99 @@ impl ExecutorSearch {
                             .into_iter(),
                     );
 
+                    if let Some(trace) = trace.as_mut() {
+                        trace.push("term", &term);
+                        trace.push("iids", iids.len());
+                    }
+
+                    let mut term_matches: HashMap<StoreObjectIID, String> = HashMap::new();
+
+                    if options.with_matches {
+                        for iid in iids.iter() {
+                            term_matches.insert(*iid, term.to_owned());
+                        }
+                    }
+
+                    // Match term as a substring of indexed words? (if n-grams are enabled for \
+                    //   collection; this performs an algebraic AND on the IIDs of all term \
+                    //   n-grams, and an algebraic OR with the term IIDs)
+                    if let Some(ref ngrams) = ngrams {
+                        if let Some(ngram_hashes) = ngrams.hashes(term) {
+                            let substring_iids = Self::resolve_ngram_iids(&kv_action, ngram_hashes);
+
+                            debug!(
+                                "got search executor substring iids: {:?} for term: {}",
+                                substring_iids, term
+                            );
+
+                            if let Some(trace) = trace.as_mut() {
+                                trace.push("substrings", substring_iids.len());
+                            }
+
+                            let substring_iids: Vec<StoreObjectIID> = substring_iids
+                                .into_iter()
+                                .filter(|substring_iid| !iids.contains(substring_iid))
+                                .collect();
+
+                            // Resolve indexed words that hold term, for IIDs that only matched \
+                            //   term as a substring (only if requested)
+                            if options.with_matches && !substring_iids.is_empty() {
+                                term_matches.extend(Self::resolve_substring_matches(
+                                    &kv_action,
+                                    &fst_action,
+                                    term,
+                                    &substring_iids,
+                                ));
+                            }
+
+                            iids.extend(substring_iids);
+                        }
+                    }
+
+                    // Expand term with its synonyms? (this performs an algebraic OR between the \
+                    //   term IIDs and the IIDs of each of its synonyms)
+                    if let Some(synonyms) =
+                        LexerSynonyms::expand(collection.as_str(), lexer.locale(), &term)
+                    {
+                        let mut synonyms_traced = Vec::new();
+
+                        for synonym in synonyms {
+                            let (synonym_iids, synonym_words) = Self::resolve_synonym_iids(
+                                &kv_action,
+                                collection.as_str(),
+                                synonym_mode,
+                                &synonym,
+                            );
+
+                            debug!(
+                                "got search executor synonym iids: {:?} for synonym: {:?}",
+                                synonym_iids, synonym
+                            );
+
+                            if trace.is_some() {
+                                synonyms_traced.push(format!(
+                                    "{}:{}",
+                                    synonym.join("+"),
+                                    synonym_iids.len()
+                                ));
+                            }
+
+                            for synonym_iid in synonym_iids {
+                                if !iids.contains(&synonym_iid) {
+                                    iids.insert(synonym_iid);
+
+                                    if options.with_matches {
+                                        term_matches.insert(synonym_iid, synonym_words.join("+"));
+                                    }
+                                }
+                            }
+                        }
+
+                        if let Some(trace) = trace.as_mut() {
+                            trace.push("synonyms", synonyms_traced.join(","));
+                        }
+                    }
+
                     // No IIDs? Try to complete with a suggested alternate word
                     // Notice: this may sound dirty to try generating as many results as the \
                     //   'retain_word_objects' value, but as we do not know if another lexed word \
@@ -82,6 +267,7 @@ impl ExecutorSearch {
                             fst_action.suggest_words(&term, alternates_try + 1, Some(1))
                         {
                             let mut iids_new_len = iids.len();
+                            let mut alternates_traced = Vec::new();
 
                             // This loop will be broken early if we get enough results at some \
                             //   iteration
@@ -97,6 +283,14 @@ impl ExecutorSearch {
                                     .get_term_to_iids(StoreTermHash::from(&suggested_word))
                                     .unwrap_or(None)
                                 {
+                                    if trace.is_some() {
+                                        alternates_traced.push(format!(
+                                            "{}:{}",
+                                            suggested_word,
+                                            suggested_iids.len()
+                                        ));
+                                    }
+
                                     for suggested_iid in suggested_iids {
                                         // Do not append the same IID twice (can happen a lot \
                                         //   when completing from suggested results that point \
@@ -104,6 +298,13 @@ impl ExecutorSearch {
                                         if !iids.contains(&suggested_iid) {
                                             iids.insert(suggested_iid);
 
+                                            if options.with_matches {
+                                                term_matches.insert(
+                                                    suggested_iid,
+                                                    suggested_word.to_owned(),
+                                                );
+                                            }
+
                                             iids_new_len += 1;
 
                                             // Higher limit now reached? Stop acquiring new \
@@ -125,6 +326,10 @@ impl ExecutorSearch {
                                 "done completing results for term: {}, now {} results",
                                 term, iids_new_len
                             );
+
+                            if let Some(trace) = trace.as_mut() {
+                                trace.push("alternates", alternates_traced.join(","));
+                            }
                         } else {
                             debug!("did not get any completed word for term: {}", term);
                         }
@@ -144,6 +349,22 @@ impl ExecutorSearch {
                         found_iids, term
                     );
 
+                    if let Some(trace) = trace.as_mut() {
+                        trace.push("intersection", found_iids.len());
+                    }
+
+                    // Append the word that matched this term to each found IID matches
+                    if options.with_matches {
+                        for found_iid in found_iids.iter() {
+                            if let Some(term_match) = term_matches.get(found_iid) {
+                                found_matches
+                                    .entry(*found_iid)
+                                    .or_default()
+                                    .push(term_match.to_owned());
+                            }
+                        }
+                    }
+
                     // No IID found? (stop there)
                     if found_iids.is_empty() {
                         info!(
@@ -151,25 +372,183 @@ impl ExecutorSearch {
                             term
                         );
 
+                        terms_evaluated = index + 1;
+
                         break 'lexing;
                     }
                 }
 
+                // Expand wildcard terms to the indexed words they match, and perform an \
+                //   algebraic OR on the IIDs of expanded words, then an algebraic AND with found \
+                //   IIDs (only if terms were all evaluated and matched some IIDs, if any)
+                if terms.is_empty() || !found_iids.is_empty() {
+                    'wildcards: for wildcard in options.wildcards.iter() {
+                        let expanded_words = fst_action.list_words(
+                            APP_CONF.channel.search.query_wildcard_expand_maximum,
+                            0,
+                            wildcard.prefix.as_deref(),
+                            Some(&wildcard.pattern),
+                            None,
+                        )?;
+
+                        debug!(
+                            "got expanded words: {:?} for wildcard: {}",
+                            expanded_words, wildcard.term
+                        );
+
+                        let mut iids = LinkedHashSet::new();
+                        let mut wildcard_matches: HashMap<StoreObjectIID, String> = HashMap::new();
+                        let mut expansions_traced = Vec::new();
+
+                        for expanded_word in expanded_words {
+                            let expanded_iids = kv_action
+                                .get_term_to_iids(StoreTermHash::from(&expanded_word))
+                                .unwrap_or(None)
+                                .unwrap_or_default();
+
+                            if trace.is_some() {
+                                expansions_traced.push(format!(
+                                    "{}:{}",
+                                    expanded_word,
+                                    expanded_iids.len()
+                                ));
+                            }
+
+                            for expanded_iid in expanded_iids {
+                                if !iids.contains(&expanded_iid) {
+                                    iids.insert(expanded_iid);
+
+                                    if options.with_matches {
+                                        wildcard_matches
+                                            .insert(expanded_iid, expanded_word.to_owned());
+                                    }
+                                }
+                            }
+                        }
+
+                        // Intersect expanded IIDs with previous batch
+                        if found_iids.is_empty() {
+                            found_iids = iids;
+                        } else {
+                            found_iids = found_iids.intersection(&iids).copied().collect();
+                        }
+
+                        if let Some(trace) = trace.as_mut() {
+                            trace.push("wildcard", &wildcard.term);
+                            trace.push("expansions", expansions_traced.join(","));
+                            trace.push("intersection", found_iids.len());
+                        }
+
+                        // Append the word that matched this wildcard to each found IID matches
+                        if options.with_matches {
+                            for found_iid in found_iids.iter() {
+                                if let Some(wildcard_match) = wildcard_matches.get(found_iid) {
+                                    found_matches
+                                        .entry(*found_iid)
+                                        .or_default()
+                                        .push(wildcard_match.to_owned());
+                                }
+                            }
+                        }
+
+                        // No IID found? (stop there)
+                        if found_iids.is_empty() {
+                            info!(
+                                "stop search executor as no iid was found in common for \
+                                    wildcard: {}",
+                                wildcard.term
+                            );
+
+                            break 'wildcards;
+                        }
+                    }
+                }
+
+                if let Some(trace) = trace.as_mut() {
+                    // Terms that were not evaluated, as the intersection went empty before them
+                    let skipped: Vec<&str> = terms[terms_evaluated..]
+                        .iter()
+                        .map(|(term, _)| term.as_str())
+                        .collect();
+
+                    if !skipped.is_empty() {
+                        trace.push("skipped", skipped.join(","));
+                    }
+                    if let Some(dropped) = lexer.dropped() {
+                        trace.push("dropped", dropped.join(","));
+                    }
+                }
+
+                // Filter and order found IIDs on their sort values? (this must be done before \
+                //   paging, as paging applies to the final ordered list of IIDs)
+                let found_iids: Vec<StoreObjectIID> =
+                    if options.range.is_some() || options.order.is_some() {
+                        Self::sort_iids(
+                            &kv_action,
+                            found_iids,
+                            options.order.as_ref(),
+                            options.range.as_ref(),
+                        )
+                    } else {
+                        found_iids.into_iter().collect()
+                    };
+
+                if let Some(trace) = trace.as_mut() {
+                    trace.push("found", found_iids.len());
+                }
+
+                // Resume from cursor? (this skips all IIDs up to the cursor IID, or up to the \
+                //   cursor position if the cursor IID cannot be found anymore)
+                let start_usize = match options.cursor.as_ref() {
+                    Some(cursor) => {
+                        // Cursor is invalid? (eg. it was not issued by a search query)
+                        let (cursor_iid, cursor_position) = Self::decode_cursor(cursor).ok_or(())?;
+
+                        found_iids
+                            .iter()
+                            .position(|found_iid| *found_iid == cursor_iid)
+                            .unwrap_or(cursor_position)
+                            + 1
+                    }
+                    None => 0,
+                };
+
                 // Resolve OIDs from IIDs
                 // Notice: we also proceed paging from there
-                let (limit_usize, offset_usize) = (limit as usize, offset as usize);
+                let (limit_usize, offset_usize) = (options.limit as usize, options.offset as usize);
                 let mut result_oids = Vec::with_capacity(limit_usize);
+                let mut last_paged = None;
+                let mut results_count = 0;
 
-                'paging: for (index, found_iid) in found_iids.iter().skip(offset_usize).enumerate()
+                'paging: for (index, (position, found_iid)) in found_iids
+                    .iter()
+                    .enumerate()
+                    .skip(start_usize + offset_usize)
+                    .enumerate()
                 {
                     // Stop there?
                     if index >= limit_usize {
                         break 'paging;
                     }
 
+                    last_paged = Some((*found_iid, position));
+
                     // Read IID-to-OID for this found IID
                     if let Ok(Some(oid)) = kv_action.get_iid_to_oid(*found_iid) {
                         result_oids.push(oid);
+                        results_count += 1;
+
+                        // Append matched words right after OID? (as a separate field, so that \
+                        //   it can never be mixed up with the OID; format: \
+                        //   'matches(<word>[,<word>]*)')
+                        if options.with_matches {
+                            let oid_matches = found_matches
+                                .get(found_iid)
+                                .map(|matches| matches.join(","))
+                                .unwrap_or_default();
+
+                            result_oids.push(format!("matches({})", oid_matches));
+                        }
                     } else {
                         error!("failed getting search executor iid-to-oid");
                     }
@@ -177,6 +556,36 @@ impl ExecutorSearch {
 
                 info!("got search executor final oids: {:?}", result_oids);
 
+                if let Some(trace) = trace.as_mut() {
+                    trace.push("results", results_count);
+                }
+
+                // Prepend leading fields? (they always come first and in this order, so that \
+                //   they can never be mistaken for an OID)
+                let mut leading_fields = Vec::new();
+
+                // Total number of found IIDs (format: 'total(<count>)')
+                if options.with_total {
+                    leading_fields.push(format!("total({})", found_iids.len()));
+                }
+
+                // Cursor to resume from (format: 'cursor(<cursor>)'; the cursor is left empty \
+                //   if there are no more IIDs after this page)
+                if options.with_cursor {
+                    let cursor = last_paged
+                        .filter(|(_, last_position)| last_position + 1 < found_iids.len())
+                        .map(|(last_iid, last_position)| {
+                            Self::encode_cursor(last_iid, last_position).to_value()
+                        })
+                        .unwrap_or_default();
+
+                    leading_fields.push(format!("cursor({})", cursor));
+                }
+
+                if !leading_fields.is_empty() {
+                    result_oids.splice(0..0, leading_fields);
+                }
+
                 return Ok(if !result_oids.is_empty() {
                     Some(result_oids)
                 } else {
@@ -187,4 +596,232 @@ impl ExecutorSearch {
 
         Err(())
     }
+
+    fn encode_cursor(iid: StoreObjectIID, position: usize) -> QuerySearchCursor {
+        // Format: '<iid><position>' (both encoded as 32 bits big-endian integers)
+        let mut cursor_bytes = iid.to_be_bytes().to_vec();
+
+        cursor_bytes.extend_from_slice(&(position as u32).to_be_bytes());
+
+        QuerySearchCursor::from_bytes(&cursor_bytes)
+    }
+
+    fn decode_cursor(cursor: &QuerySearchCursor) -> Option<(StoreObjectIID, usize)> {
+        let cursor_bytes = cursor.as_bytes();
+
+        if cursor_bytes.len() == 8 {
+            let (mut iid_bytes, mut position_bytes) = ([0; 4], [0; 4]);
+
+            iid_bytes.copy_from_slice(&cursor_bytes[0..4]);
+            position_bytes.copy_from_slice(&cursor_bytes[4..8]);
+
+            Some((
+                StoreObjectIID::from_be_bytes(iid_bytes),
+                u32::from_be_bytes(position_bytes) as usize,
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn resolve_synonym_iids(
+        kv_action: &StoreKVAction,
+        collection: &str,
+        mode: TokenLexerMode,
+        synonym: &[String],
+    ) -> (LinkedHashSet<StoreObjectIID>, Vec<String>) {
+        // Resolve IIDs for all words in synonym, and perform an algebraic AND on them (as a \
+        //   synonym may be a phrase made of multiple words); lexed synonym words are returned \
+        //   along, as they are the indexed words that got matched
+        // Notice: synonym words are lexed the same way than query terms were (ie. normalized, \
+        //   and stemmed if enabled), so that they match indexed words.
+        let mut synonym_iids: LinkedHashSet<StoreObjectIID> = LinkedHashSet::new();
+        let mut synonym_words = Vec::new();
+
+        let synonym_text = synonym.join(" ");
+
+        let synonym_lexer = match TokenLexerBuilder::from(mode, collection, &synonym_text) {
+            Ok(synonym_lexer) => synonym_lexer,
+            Err(_) => return (synonym_iids, synonym_words),
+        };
+
+        for (index, (synonym_term, synonym_term_hashed)) in synonym_lexer.enumerate() {
+            synonym_words.push(synonym_term);
+
+            let word_iids = LinkedHashSet::from_iter(
+                kv_action
+                    .get_term_to_iids(synonym_term_hashed)
+                    .unwrap_or(None)
+                    .unwrap_or_default()
+                    .into_iter(),
+            );
+
+            if index == 0 {
+                synonym_iids = word_iids;
+            } else {
+                synonym_iids = synonym_iids.intersection(&word_iids).copied().collect();
+            }
+
+            if synonym_iids.is_empty() {
+                break;
+            }
+        }
+
+        (synonym_iids, synonym_words)
+    }
+
+    fn resolve_substring_matches(
+        kv_action: &StoreKVAction,
+        fst_action: &StoreFSTAction,
+        term: &str,
+        substring_iids: &[StoreObjectIID],
+    ) -> HashMap<StoreObjectIID, String> {
+        // Map each IID to an indexed word that holds term as a substring (eg. 'notebook' for \
+        //   'book'), as this is the word that got matched; words holding term are looked up in \
+        //   the graph, up to the matches scan maximum (the term itself is used as a fallback if \
+        //   no word could be found for an IID)
+        let mut substring_matches: HashMap<StoreObjectIID, String> = HashMap::new();
+
+        let substring_iids_set: HashSet<StoreObjectIID> = substring_iids.iter().copied().collect();
+
+        fst_action.find_words_containing(
+            term,
+            APP_CONF.channel.search.query_matches_scan_maximum,
+            |substring_word| {
+                if let Some(word_iids) = kv_action
+                    .get_term_to_iids(StoreTermHash::from(substring_word))
+                    .unwrap_or(None)
+                {
+                    for word_iid in word_iids {
+                        if substring_iids_set.contains(&word_iid)
+                            && !substring_matches.contains_key(&word_iid)
+                        {
+                            substring_matches.insert(word_iid, substring_word.to_owned());
+                        }
+                    }
+                }
+
+                // Continue until a word was found for each IID
+                substring_matches.len() < substring_iids.len()
+            },
+        );
+
+        for substring_iid in substring_iids {
+            substring_matches
+                .entry(*substring_iid)
+                .or_insert_with(|| term.to_owned());
+        }
+
+        substring_matches
+    }
+
+    fn resolve_ngram_iids(
+        kv_action: &StoreKVAction,
+        ngram_hashes: Vec<StoreTermHashed>,
+    ) -> Vec<StoreObjectIID> {
+        let mut ngram_iids: Option<Vec<StoreObjectIID>> = None;
+
+        // Intersect IIDs of all n-grams (an IID must hold all n-grams to match; stop as soon \
+        //   as there are no IIDs left)
+        for ngram_hash in ngram_hashes {
+            let iids = kv_action
+                .get_term_to_iids(ngram_hash)
+                .unwrap_or(None)
+                .unwrap_or_default();
+
+            ngram_iids = Some(match ngram_iids {
+                Some(ngram_iids) => {
+                    let iids: HashSet<StoreObjectIID> = HashSet::from_iter(iids.into_iter());
+
+                    ngram_iids
+                        .into_iter()
+                        .filter(|iid| iids.contains(iid))
+                        .collect()
+                }
+                None => iids,
+            });
+
+            if ngram_iids.as_ref().map(|iids| iids.is_empty()) == Some(true) {
+                break;
+            }
+        }
+
+        ngram_iids.unwrap_or_default()
+    }
+
+    fn sort_iids(
+        kv_action: &StoreKVAction,
+        found_iids: LinkedHashSet<StoreObjectIID>,
+        order: Option<&QuerySearchOrder>,
+        range: Option<&QuerySearchRange>,
+    ) -> Vec<StoreObjectIID> {
+        let (order_key_hashed, range_key_hashed) = (
+            order.map(|order| StoreSortKeyHash::from(&order.key)),
+            range.map(|range| StoreSortKeyHash::from(&range.key)),
+        );
+
+        let mut sorted_iids: Vec<(StoreObjectIID, Option<StoreSortValue>)> =
+            Vec::with_capacity(found_iids.len());
+
+        for found_iid in found_iids {
+            let iid_sorts = kv_action
+                .get_iid_to_sorts(found_iid)
+                .unwrap_or(None)
+                .unwrap_or_default();
+
+            let find_sort_value = |key_hashed: StoreSortKeyHashed| {
+                iid_sorts
+                    .iter()
+                    .find(|iid_sort| iid_sort.0 == key_hashed)
+                    .map(|iid_sort| iid_sort.1)
+            };
+
+            // Filter out IIDs out of range (or that do not have a value for the range key)
+            if let (Some(range), Some(range_key_hashed)) = (range, range_key_hashed) {
+                match find_sort_value(range_key_hashed) {
+                    Some(range_value) if range.contains(range_value) => {}
+                    _ => continue,
+                }
+            }
+
+            sorted_iids.push((found_iid, order_key_hashed.and_then(find_sort_value)));
+        }
+
+        debug!(
+            "got search executor sorted iids: {:?} for order: {:?} and range: {:?}",
+            sorted_iids, order, range
+        );
+
+        // Order IIDs on their sort value?
+        // Notice: IIDs that do not have a value for the order key always come last, no matter \
+        //   the direction. Sorting is stable, so that ties keep their relevance order.
+        if let Some(order) = order {
+            sorted_iids.sort_by(|a, b| match (a.1, b.1) {
+                (Some(a_value), Some(b_value)) => match order.direction {
+                    QuerySearchOrderDirection::Asc => a_value.cmp(&b_value),
+                    QuerySearchOrderDirection::Desc => b_value.cmp(&a_value),
+                },
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+
+        sorted_iids
+            .into_iter()
+            .map(|sorted_iid| sorted_iid.0)
+            .collect()
+    }
+}
+
+impl ExecutorSearchTrace {
+    fn push<T: fmt::Display>(&mut self, key: &str, value: T) {
+        self.parts.push(format!("{}({})", key, value));
+    }
+}
+
+impl fmt::Display for ExecutorSearchTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.parts.join(" "))
+    }
 }
@@ -4,10 +4,16 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use crate::lexer::token::TokenLexer;
+use hashbrown::HashSet;
+use std::cmp;
+
+use crate::lexer::token::{TokenLexer, TokenLexerBuilder, TokenLexerMode};
 use crate::query::types::{QuerySearchID, QuerySearchLimit};
 use crate::store::fst::{StoreFSTActionBuilder, StoreFSTPool};
+use crate::store::identifiers::{StoreObjectIID, StoreTermHash, StoreTermHashed};
 use crate::store::item::StoreItem;
+use crate::store::kv::{StoreKVAcquireMode, StoreKVAction, StoreKVActionBuilder, StoreKVPool};
+use crate::APP_CONF;
 
 pub struct ExecutorSuggest;
 
@@ -15,25 +21,230 @@ impl ExecutorSuggest {
     pub fn execute<'a>(
         store: StoreItem<'a>,
         _event_id: QuerySearchID,
-        mut lexer: TokenLexer<'a>,
+        lexer: TokenLexer<'a>,
         limit: QuerySearchLimit,
     ) -> Result<Option<Vec<String>>, ()> {
         if let StoreItem(collection, Some(bucket), None) = store {
-            // Important: acquire graph access read lock, and reference it in context. This \
-            //   prevents the graph from being erased while using it in this block.
+            // Important: acquire database and graph access read locks, and reference them in \
+            //   context. This prevents the database and graph from being erased while using \
+            //   them in this block.
+            general_kv_access_lock_read!();
             general_fst_access_lock_read!();
 
-            if let Ok(fst_store) = StoreFSTPool::acquire(collection, bucket) {
-                let fst_action = StoreFSTActionBuilder::access(fst_store);
+            if let (Ok(kv_store), Ok(fst_store)) = (
+                StoreKVPool::acquire(StoreKVAcquireMode::OpenOnly, collection),
+                StoreFSTPool::acquire(collection, bucket),
+            ) {
+                // Important: acquire bucket store read lock
+                executor_kv_lock_read!(kv_store);
+
+                let (kv_action, fst_action) = (
+                    StoreKVActionBuilder::access(bucket, kv_store),
+                    StoreFSTActionBuilder::access(fst_store),
+                );
+
+                // Notice: the last word is the one to be completed, while any preceding word is \
+                //   used as context (ie. completions must appear in objects with those words)
+                let words = lexer.collect::<Vec<(String, StoreTermHashed)>>();
+
+                if let Some(((word, _), context)) = words.split_last() {
+                    let context_phrase = context
+                        .iter()
+                        .map(|(context_word, _)| context_word.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(" ");
+
+                    debug!(
+                        "running suggest on word: {} with context: {}",
+                        word, context_phrase
+                    );
 
-                if let (Some(word), None) = (lexer.next(), lexer.next()) {
-                    debug!("running suggest on word: {}", word.0);
+                    let context_iids = Self::resolve_context_iids(
+                        &kv_action,
+                        collection.as_str(),
+                        &context_phrase,
+                    );
 
-                    return Ok(fst_action.suggest_words(&word.0, limit as usize, None));
+                    // No object contains all context words? No word can be suggested
+                    if let Some(ref context_iids) = context_iids {
+                        if context_iids.is_empty() {
+                            return Ok(Some(Vec::new()));
+                        }
+                    }
+
+                    // Gather more candidates than requested, so that the most popular ones can \
+                    //   be picked (as the graph yields candidates in lexicographic order)
+                    let candidates = cmp::max(
+                        limit as usize,
+                        APP_CONF.channel.search.suggest_rank_candidates,
+                    );
+
+                    // Keep scanning candidates until enough words that appear along with \
+                    //   context words are found, as most words may not appear along with them
+                    let suggested_words = if let Some(ref context_iids) = context_iids {
+                        fst_action.suggest_words_filtered(
+                            word,
+                            candidates,
+                            cmp::max(
+                                candidates,
+                                APP_CONF.channel.search.suggest_context_scan_maximum,
+                            ),
+                            None,
+                            |suggested_word| {
+                                Self::count_word_iids(
+                                    &kv_action,
+                                    suggested_word,
+                                    Some(context_iids),
+                                ) > 0
+                            },
+                        )
+                    } else {
+                        fst_action.suggest_words(word, candidates, None)
+                    };
+
+                    return Ok(suggested_words
+                        .map(|suggested_words| {
+                            Self::rank_words(
+                                &kv_action,
+                                word,
+                                suggested_words,
+                                context_iids.as_ref(),
+                                limit,
+                            )
+                        })
+                        .map(|ranked_words| {
+                            if context_phrase.is_empty() {
+                                ranked_words
+                            } else {
+                                // Suggest full phrases (quoted, as they contain multiple words)
+                                ranked_words
+                                    .into_iter()
+                                    .map(|ranked_word| {
+                                        format!("\"{} {}\"", context_phrase, ranked_word)
+                                    })
+                                    .collect()
+                            }
+                        }));
                 }
             }
         }
 
         Err(())
     }
+
+    fn rank_words(
+        kv_action: &StoreKVAction,
+        from_word: &str,
+        suggested_words: Vec<String>,
+        context_iids: Option<&HashSet<StoreObjectIID>>,
+        limit: QuerySearchLimit,
+    ) -> Vec<String> {
+        // Rank suggested words by popularity (ie. the number of objects they appear in, or the \
+        //   number of objects they share with context words if any); words that complete the \
+        //   provided word always come before typo corrections. Notice: the number of objects \
+        //   is capped to 'retain_word_objects', which is good enough here.
+        let mut ranked_words = suggested_words
+            .into_iter()
+            .filter_map(|suggested_word| {
+                let frequency = Self::count_word_iids(kv_action, &suggested_word, context_iids);
+
+                // Drop words that never appear along with context words
+                if context_iids.is_some() && frequency == 0 {
+                    None
+                } else {
+                    Some((
+                        suggested_word.starts_with(from_word),
+                        frequency,
+                        suggested_word,
+                    ))
+                }
+            })
+            .collect::<Vec<(bool, usize, String)>>();
+
+        // Notice: sort is stable, so that words with an equal rank stay in lexicographic order
+        ranked_words.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+
+        ranked_words
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, _, suggested_word)| suggested_word)
+            .collect()
+    }
+
+    fn count_word_iids(
+        kv_action: &StoreKVAction,
+        word: &str,
+        context_iids: Option<&HashSet<StoreObjectIID>>,
+    ) -> usize {
+        let iids = kv_action
+            .get_term_to_iids(StoreTermHash::from(word))
+            .unwrap_or(None)
+            .unwrap_or_default();
+
+        if let Some(context_iids) = context_iids {
+            iids.iter()
+                .filter(|iid| context_iids.contains(*iid))
+                .count()
+        } else {
+            iids.len()
+        }
+    }
+
+    fn resolve_context_iids(
+        kv_action: &StoreKVAction,
+        collection: &str,
+        context_phrase: &str,
+    ) -> Option<HashSet<StoreObjectIID>> {
+        // Intersect IIDs for all context words (ie. objects that contain all context words); \
+        //   if no context word is indexed, there is no context to narrow down suggestions with.
+        // Notice: context words are lexed the same way than pushed text was (ie. stop-words \
+        //   get removed, and words get stemmed if enabled), so that they match indexed words.
+        if context_phrase.is_empty() {
+            return None;
+        }
+
+        let context_lexer = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            collection,
+            context_phrase,
+        )
+        .ok()?;
+
+        let mut context_iids: Option<HashSet<StoreObjectIID>> = None;
+
+        for (context_word, context_word_hashed) in context_lexer {
+            let word_iids = kv_action
+                .get_term_to_iids(context_word_hashed)
+                .unwrap_or(None)
+                .unwrap_or_default();
+
+            debug!(
+                "got {} context iids for suggest word: {}",
+                word_iids.len(),
+                context_word
+            );
+
+            // Ignore words that are not indexed (ie. they cannot narrow down suggestions)
+            if word_iids.is_empty() {
+                continue;
+            }
+
+            let next_iids: HashSet<StoreObjectIID> = match context_iids {
+                Some(context_iids) => word_iids
+                    .into_iter()
+                    .filter(|iid| context_iids.contains(iid))
+                    .collect(),
+                None => word_iids.into_iter().collect(),
+            };
+
+            // No object left? Stop there (no word can co-occur with context words)
+            if next_iids.is_empty() {
+                return Some(next_iids);
+            }
+
+            context_iids = Some(next_iids);
+        }
+
+        context_iids
+    }
 }
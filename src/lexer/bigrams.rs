@@ -0,0 +1,141 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use unicode_segmentation::UnicodeSegmentation;
+use whatlang::Lang;
+
+use crate::config::options::ConfigCollection;
+
+pub struct LexerBigrams;
+
+impl LexerBigrams {
+    pub fn is_enabled(collection_config: Option<&ConfigCollection>, locale: Option<Lang>) -> bool {
+        match locale {
+            // Notice: CJK bigrams are used as a fallback for Chinese and Japanese text if their \
+            //   dictionary-based tokenizer is not compiled in (as CJK text is not split into \
+            //   words with spaces)
+            Some(Lang::Cmn) if !cfg!(feature = "tokenizer-chinese") => true,
+            Some(Lang::Jpn) if !cfg!(feature = "tokenizer-japanese") => true,
+            _ => collection_config
+                .map(|collection_config| collection_config.cjk_bigrams)
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn tokenize(text: &str) -> Vec<&str> {
+        let mut tokens = Vec::new();
+
+        // Split text into runs of CJK and non-CJK characters, where CJK runs get split into \
+        //   overlapping bigrams, and other runs get split into words
+        let (mut run_start, mut run_is_cjk) = (0, false);
+
+        for (index, character) in text.char_indices() {
+            let is_cjk = Self::is_cjk(character);
+
+            if is_cjk != run_is_cjk {
+                Self::tokenize_run(&text[run_start..index], run_is_cjk, &mut tokens);
+
+                run_start = index;
+                run_is_cjk = is_cjk;
+            }
+        }
+
+        Self::tokenize_run(&text[run_start..], run_is_cjk, &mut tokens);
+
+        tokens
+    }
+
+    fn tokenize_run<'a>(run: &'a str, is_cjk: bool, tokens: &mut Vec<&'a str>) {
+        if is_cjk {
+            let run_indices: Vec<usize> = run.char_indices().map(|(index, _)| index).collect();
+
+            // Single character? (it cannot be split into bigrams, so it is used as-is)
+            if run_indices.len() == 1 {
+                tokens.push(run);
+            } else {
+                for bigram_index in 0..(run_indices.len() - 1) {
+                    let bigram_end = run_indices
+                        .get(bigram_index + 2)
+                        .copied()
+                        .unwrap_or(run.len());
+
+                    tokens.push(&run[run_indices[bigram_index]..bigram_end]);
+                }
+            }
+        } else if !run.is_empty() {
+            tokens.extend(run.unicode_words());
+        }
+    }
+
+    fn is_cjk(character: char) -> bool {
+        matches!(
+            character,
+            // Han (CJK Unified Ideographs, plus extensions and compatibility ideographs)
+            '\u{3400}'..='\u{4DBF}'
+                | '\u{4E00}'..='\u{9FFF}'
+                | '\u{F900}'..='\u{FAFF}'
+                | '\u{20000}'..='\u{2FA1F}'
+                // Kana (Hiragana, Katakana and half-width Katakana)
+                | '\u{3040}'..='\u{309F}'
+                | '\u{30A0}'..='\u{30FF}'
+                | '\u{31F0}'..='\u{31FF}'
+                | '\u{FF66}'..='\u{FF9F}'
+                // Hangul (Jamo, compatibility Jamo and syllables)
+                | '\u{1100}'..='\u{11FF}'
+                | '\u{3130}'..='\u{318F}'
+                | '\u{AC00}'..='\u{D7AF}'
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tokenizes_cjk_runs_into_bigrams() {
+        assert_eq!(
+            LexerBigrams::tokenize("東京都に住む"),
+            vec!["東京", "京都", "都に", "に住", "住む"]
+        );
+        assert_eq!(
+            LexerBigrams::tokenize("Sonic 搜索引擎, fast!"),
+            vec!["Sonic", "搜索", "索引", "引擎", "fast"]
+        );
+        assert_eq!(LexerBigrams::tokenize("猫。犬"), vec!["猫", "犬"]);
+        assert_eq!(
+            LexerBigrams::tokenize("한국어 검색"),
+            vec!["한국", "국어", "검색"]
+        );
+    }
+
+    #[test]
+    fn it_enables_bigrams_per_locale() {
+        let config = toml::from_str::<ConfigCollection>("cjk_bigrams = true").unwrap();
+
+        assert_eq!(
+            LexerBigrams::is_enabled(None, Some(Lang::Cmn)),
+            !cfg!(feature = "tokenizer-chinese")
+        );
+        assert_eq!(
+            LexerBigrams::is_enabled(None, Some(Lang::Jpn)),
+            !cfg!(feature = "tokenizer-japanese")
+        );
+        assert!(!LexerBigrams::is_enabled(None, Some(Lang::Kor)));
+        assert!(!LexerBigrams::is_enabled(None, None));
+        assert!(LexerBigrams::is_enabled(Some(&config), Some(Lang::Kor)));
+        assert!(LexerBigrams::is_enabled(Some(&config), Some(Lang::Cmn)));
+    }
+
+    #[test]
+    fn it_tokenizes_non_cjk_text_into_words() {
+        assert_eq!(
+            LexerBigrams::tokenize("The quick brown fox"),
+            vec!["The", "quick", "brown", "fox"]
+        );
+        assert!(LexerBigrams::tokenize("").is_empty());
+    }
+}
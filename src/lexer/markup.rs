@@ -0,0 +1,522 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::borrow::Cow;
+
+use crate::query::types::QueryPushFormat;
+
+pub struct LexerMarkup;
+
+const HTML_SKIPPED_TAGS: [&str; 2] = ["script", "style"];
+const HTML_KEPT_ATTRIBUTES: [&str; 2] = ["alt", "title"];
+const HTML_ENTITY_MAXIMUM_LENGTH: usize = 32;
+
+static HTML_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("shy", '\u{ad}'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("hellip", '…'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("agrave", 'à'),
+    ("aacute", 'á'),
+    ("acirc", 'â'),
+    ("auml", 'ä'),
+    ("ccedil", 'ç'),
+    ("egrave", 'è'),
+    ("eacute", 'é'),
+    ("ecirc", 'ê'),
+    ("euml", 'ë'),
+    ("icirc", 'î'),
+    ("iuml", 'ï'),
+    ("ntilde", 'ñ'),
+    ("ocirc", 'ô'),
+    ("ouml", 'ö'),
+    ("ugrave", 'ù'),
+    ("uacute", 'ú'),
+    ("ucirc", 'û'),
+    ("uuml", 'ü'),
+    ("szlig", 'ß'),
+];
+
+impl LexerMarkup {
+    pub fn strip(text: &str, format: QueryPushFormat) -> Cow<'_, str> {
+        match format {
+            QueryPushFormat::Text => Cow::Borrowed(text),
+            QueryPushFormat::Html => Cow::Owned(Self::strip_html(text)),
+
+            // Notice: Markdown may embed HTML, so HTML gets stripped from the stripped Markdown
+            QueryPushFormat::Markdown => Cow::Owned(Self::strip_html(&Self::strip_markdown(text))),
+        }
+    }
+
+    fn strip_html(text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(index) = rest.find(['<', '&']) {
+            stripped.push_str(&rest[..index]);
+
+            rest = if rest[index..].starts_with('&') {
+                Self::decode_html_entity(&rest[index..], &mut stripped)
+            } else {
+                Self::strip_html_tag(&rest[index..], &mut stripped)
+            };
+        }
+
+        stripped.push_str(rest);
+
+        stripped
+    }
+
+    fn strip_html_tag<'a>(rest: &'a str, stripped: &mut String) -> &'a str {
+        // Comment? (drop it as a whole)
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            stripped.push(' ');
+
+            return comment
+                .find("-->")
+                .map(|comment_end| &comment[(comment_end + 3)..])
+                .unwrap_or("");
+        }
+
+        // Not a tag? (eg. 'a < b' in text, which is kept as-is)
+        let tag_end = match Self::find_html_tag_end(rest) {
+            Some(tag_end) => tag_end,
+            None => {
+                stripped.push('<');
+
+                return &rest[1..];
+            }
+        };
+
+        let tag = &rest[1..tag_end];
+        let tag_is_closing = tag.starts_with('/');
+
+        let tag_name = tag
+            .trim_start_matches('/')
+            .split(|character: char| {
+                character.is_whitespace() || character == '/' || character == '>'
+            })
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        // Tags are replaced with spaces, so that words in consecutive blocks do not get merged, \
+        //   although alternate texts and titles are kept (eg. for images)
+        stripped.push(' ');
+
+        if !tag_is_closing {
+            Self::keep_html_attributes(&tag[tag_name.len()..], stripped);
+        }
+
+        let rest = &rest[(tag_end + 1)..];
+
+        // Drop content of skipped tags (eg. scripts and styles), up to their closing tag
+        if !tag_is_closing && HTML_SKIPPED_TAGS.contains(&tag_name.as_str()) {
+            let closing_tag = format!("</{}", tag_name);
+
+            rest.to_ascii_lowercase()
+                .find(&closing_tag)
+                .map(|closing_index| &rest[closing_index..])
+                .unwrap_or("")
+        } else {
+            rest
+        }
+    }
+
+    fn find_html_tag_end(rest: &str) -> Option<usize> {
+        // Tags start with a letter, or with '/' (closing tag), '!' (doctype) or '?' \
+        //   (processing instruction)
+        let mut characters = rest[1..].chars();
+
+        let is_tag = match characters.next() {
+            Some('/') => characters
+                .next()
+                .map(|character| character.is_ascii_alphabetic())
+                .unwrap_or(false),
+            Some('!') | Some('?') => true,
+            Some(character) => character.is_ascii_alphabetic(),
+            None => false,
+        };
+
+        if !is_tag {
+            return None;
+        }
+
+        // Find tag end (ignoring '>' within quoted attribute values)
+        let mut quote = None;
+
+        for (index, character) in rest.char_indices().skip(1) {
+            match (quote, character) {
+                (None, '"') | (None, '\'') => quote = Some(character),
+                (None, '>') => return Some(index),
+                (Some(quote_character), _) if quote_character == character => quote = None,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn keep_html_attributes(attributes: &str, stripped: &mut String) {
+        let mut rest = attributes;
+
+        loop {
+            rest = rest.trim_start_matches(|character: char| {
+                character.is_whitespace() || character == '/'
+            });
+
+            let name_end = rest
+                .find(|character: char| character.is_whitespace() || character == '=')
+                .unwrap_or(rest.len());
+
+            if name_end == 0 {
+                break;
+            }
+
+            let name = &rest[..name_end];
+
+            rest = rest[name_end..].trim_start();
+
+            // Attribute has a value? (either quoted or unquoted)
+            if let Some(value_rest) = rest.strip_prefix('=') {
+                let value_rest = value_rest.trim_start();
+
+                let (value, value_end) = match value_rest.chars().next() {
+                    Some(quote @ '"') | Some(quote @ '\'') => {
+                        let value_end = value_rest[1..]
+                            .find(quote)
+                            .map(|value_end| value_end + 1)
+                            .unwrap_or(value_rest.len());
+
+                        (
+                            &value_rest[1..value_end],
+                            (value_end + 1).min(value_rest.len()),
+                        )
+                    }
+                    _ => {
+                        let value_end = value_rest
+                            .find(char::is_whitespace)
+                            .unwrap_or(value_rest.len());
+
+                        (&value_rest[..value_end], value_end)
+                    }
+                };
+
+                if HTML_KEPT_ATTRIBUTES.contains(&name.to_ascii_lowercase().as_str()) {
+                    stripped.push_str(&Self::strip_html(value));
+                    stripped.push(' ');
+                }
+
+                rest = &value_rest[value_end..];
+            }
+        }
+    }
+
+    fn decode_html_entity<'a>(rest: &'a str, stripped: &mut String) -> &'a str {
+        // Format: '&<name>;', '&#<decimal>;' or '&#x<hexadecimal>;'
+        let decoded = rest
+            .char_indices()
+            .take(HTML_ENTITY_MAXIMUM_LENGTH)
+            .find(|(_, character)| *character == ';')
+            .and_then(|(entity_end, _)| {
+                let entity = &rest[1..entity_end];
+
+                let character = if let Some(code) = entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                {
+                    u32::from_str_radix(code, 16).ok().and_then(char::from_u32)
+                } else if let Some(code) = entity.strip_prefix('#') {
+                    code.parse::<u32>().ok().and_then(char::from_u32)
+                } else {
+                    HTML_ENTITIES
+                        .iter()
+                        .find(|(name, _)| *name == entity)
+                        .map(|(_, character)| *character)
+                };
+
+                character.map(|character| (character, entity_end))
+            });
+
+        // Unknown entity? (keep it as-is)
+        match decoded {
+            Some((character, entity_end)) => {
+                stripped.push(character);
+
+                &rest[(entity_end + 1)..]
+            }
+            None => {
+                stripped.push('&');
+
+                &rest[1..]
+            }
+        }
+    }
+
+    fn strip_markdown(text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        let mut in_code_block = false;
+
+        for line in text.lines() {
+            let line = line.trim_start();
+
+            // Code fence? (drop fence lines, but keep code, as it may hold searched words)
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code_block = !in_code_block;
+            } else if in_code_block {
+                stripped.push_str(line);
+            } else if !Self::is_markdown_link_definition(line) {
+                Self::strip_markdown_inline(
+                    Self::strip_markdown_block_markers(line),
+                    &mut stripped,
+                );
+            }
+
+            stripped.push('\n');
+        }
+
+        stripped
+    }
+
+    fn strip_markdown_block_markers(line: &str) -> &str {
+        let mut line = line;
+
+        // Strip nested block markers (eg. '> - ## Title' in a quoted list)
+        loop {
+            let line_content = if let Some(quoted) = line.strip_prefix('>') {
+                quoted
+            } else if line.starts_with('#') {
+                let heading = line.trim_start_matches('#');
+
+                if heading.is_empty() || heading.starts_with(char::is_whitespace) {
+                    heading
+                } else {
+                    break;
+                }
+            } else if let Some(item) = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|marker| line.strip_prefix(marker))
+            {
+                item
+            } else {
+                let digits =
+                    line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+                if digits > 0
+                    && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+                {
+                    &line[(digits + 2)..]
+                } else {
+                    break;
+                }
+            };
+
+            line = line_content.trim_start();
+        }
+
+        // Horizontal rules and heading underlines hold no text (eg. '---' or '===')
+        if line.len() >= 3
+            && line
+                .chars()
+                .all(|character| matches!(character, '-' | '*' | '_' | '=' | ' '))
+        {
+            ""
+        } else {
+            line
+        }
+    }
+
+    fn strip_markdown_inline(text: &str, stripped: &mut String) {
+        let (mut rest, mut previous) = (text, None);
+
+        while let Some(character) = rest.chars().next() {
+            let mut advance = character.len_utf8();
+
+            match character {
+                '\\' => {
+                    // Escaped character (keep it as-is)
+                    if let Some(escaped) = rest[advance..].chars().next() {
+                        stripped.push(escaped);
+
+                        advance += escaped.len_utf8();
+                    }
+                }
+                '!' | '[' => {
+                    // Link or image? (keep its text and title, but drop its destination)
+                    match Self::parse_markdown_link(rest) {
+                        Some((label, title, length)) => {
+                            Self::strip_markdown_inline(label, stripped);
+
+                            if let Some(title) = title {
+                                stripped.push(' ');
+                                stripped.push_str(title);
+                            }
+
+                            advance = length;
+                        }
+                        None => stripped.push(character),
+                    }
+                }
+                '*' | '_' | '~' | '`' => {
+                    // Emphasis and code markers get dropped, unless they are within a word \
+                    //   (eg. 'snake_case')
+                    let is_within_word = previous.map(char::is_alphanumeric).unwrap_or(false)
+                        && rest[advance..]
+                            .chars()
+                            .next()
+                            .map(char::is_alphanumeric)
+                            .unwrap_or(false);
+
+                    if is_within_word {
+                        stripped.push(character);
+                    }
+                }
+                '|' => stripped.push(' '),
+                _ => stripped.push(character),
+            }
+
+            previous = Some(character);
+            rest = &rest[advance..];
+        }
+    }
+
+    fn parse_markdown_link(rest: &str) -> Option<(&str, Option<&str>, usize)> {
+        // Format: '[<label>](<destination> "<title>")', '[<label>][<reference>]' or \
+        //   '[<label>]', prefixed with '!' for images (whose label is the alternate text)
+        let label_start = if rest.starts_with("![") {
+            2
+        } else if rest.starts_with('[') {
+            1
+        } else {
+            return None;
+        };
+
+        let label_end = label_start + Self::find_closing(&rest[label_start..], '[', ']')?;
+        let label = &rest[label_start..label_end];
+
+        let after_label = &rest[(label_end + 1)..];
+
+        if let Some(destination) = after_label.strip_prefix('(') {
+            let destination_end = Self::find_closing(destination, '(', ')')?;
+
+            let title = destination[..destination_end]
+                .trim()
+                .split_once(char::is_whitespace)
+                .map(|(_, title)| title.trim().trim_matches(|c: char| c == '"' || c == '\''))
+                .filter(|title| !title.is_empty());
+
+            Some((label, title, label_end + destination_end + 3))
+        } else if let Some(reference) = after_label.strip_prefix('[') {
+            let reference_end = Self::find_closing(reference, '[', ']')?;
+
+            Some((label, None, label_end + reference_end + 3))
+        } else {
+            Some((label, None, label_end + 1))
+        }
+    }
+
+    fn find_closing(text: &str, opening: char, closing: char) -> Option<usize> {
+        let mut depth = 0;
+
+        for (index, character) in text.char_indices() {
+            if character == opening {
+                depth += 1;
+            } else if character == closing {
+                if depth == 0 {
+                    return Some(index);
+                }
+
+                depth -= 1;
+            }
+        }
+
+        None
+    }
+
+    fn is_markdown_link_definition(line: &str) -> bool {
+        // Format: '[<reference>]: <destination>' (holds no text)
+        line.starts_with('[')
+            && line
+                .find("]:")
+                .map(|definition_index| !line[1..definition_index].contains(']'))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_strips_html() {
+        assert_eq!(
+            LexerMarkup::strip(
+                "<p class=\"intro\">Caf&eacute; &amp; <b>bar</b></p><script>var x = 1;</script>\
+                 <img src=\"fox.png\" alt=\"Quick fox\"><!-- hidden --><a title='Home'>a < b</a>",
+                QueryPushFormat::Html
+            )
+            .split_whitespace()
+            .collect::<Vec<&str>>(),
+            vec!["Café", "&", "bar", "Quick", "fox", "Home", "a", "<", "b"]
+        );
+        assert_eq!(
+            LexerMarkup::strip(
+                "<STYLE>p { color: red; }</STYLE>&#x41;&#66;&unknown;",
+                QueryPushFormat::Html
+            ),
+            "  AB&unknown;"
+        );
+    }
+
+    #[test]
+    fn it_strips_markdown() {
+        assert_eq!(
+            LexerMarkup::strip(
+                "# Sonic *search*\n\n> - A **fast** [backend](https://sonic.rs \"Sonic\")\n\n\
+                 ![Logo](logo.png)\n\n---\n[sonic]: https://sonic.rs\n\n`snake_case` <br>",
+                QueryPushFormat::Markdown
+            )
+            .split_whitespace()
+            .collect::<Vec<&str>>(),
+            vec![
+                "Sonic",
+                "search",
+                "A",
+                "fast",
+                "backend",
+                "Sonic",
+                "Logo",
+                "snake_case"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keeps_text_as_is() {
+        assert_eq!(
+            LexerMarkup::strip("<b>bold</b> **text**", QueryPushFormat::Text),
+            "<b>bold</b> **text**"
+        );
+    }
+}
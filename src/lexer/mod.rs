@@ -4,7 +4,16 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+mod bigrams;
+mod normalizer;
+mod patterns;
+mod stemmer;
 mod stopwords;
+mod tokenizer;
+mod transliterator;
 
+pub mod markup;
+pub mod ngrams;
 pub mod ranges;
+pub mod synonyms;
 pub mod token;
@@ -0,0 +1,126 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use crate::config::options::ConfigCollection;
+use crate::store::identifiers::{StoreTermHash, StoreTermHashed};
+use crate::APP_CONF;
+
+pub struct LexerNGrams {
+    size: usize,
+    max_word_length: usize,
+}
+
+// Notice: n-grams are hashed with a prefix that the lexer never yields in words, so that an \
+//   n-gram never collides with an indexed word of the same characters (eg. 'boo')
+const NGRAM_HASH_PREFIX: char = '\u{1}';
+
+impl LexerNGrams {
+    pub fn from(collection: &str) -> Option<LexerNGrams> {
+        APP_CONF
+            .collection
+            .get(collection)
+            .and_then(Self::from_config)
+    }
+
+    pub fn from_config(collection_config: &ConfigCollection) -> Option<LexerNGrams> {
+        // N-grams must be explicitly enabled for the collection, as they get stored in the \
+        //   index (ie. toggling them requires the collection to be re-indexed)
+        if collection_config.ngrams && collection_config.ngram_size > 0 {
+            Some(LexerNGrams {
+                size: collection_config.ngram_size,
+                max_word_length: collection_config.ngram_max_word_length,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn hashes(&self, word: &str) -> Option<Vec<StoreTermHashed>> {
+        let chars: Vec<char> = word.chars().collect();
+
+        // Words that are either too short or too long do not get split into n-grams (this \
+        //   bounds the number of n-grams that get stored for each word)
+        if chars.len() < self.size || chars.len() > self.max_word_length {
+            return None;
+        }
+
+        let mut hashes: Vec<StoreTermHashed> = Vec::with_capacity(chars.len() - self.size + 1);
+
+        for window in chars.windows(self.size) {
+            let mut ngram = String::with_capacity(window.len() + 1);
+
+            ngram.push(NGRAM_HASH_PREFIX);
+            ngram.extend(window);
+
+            let ngram_hash = StoreTermHash::from(&ngram);
+
+            // Return unique n-grams (eg. 'aaaa' holds 'aaa' twice)
+            if !hashes.contains(&ngram_hash) {
+                hashes.push(ngram_hash);
+            }
+        }
+
+        Some(hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_words_into_ngrams() {
+        let ngrams = LexerNGrams {
+            size: 3,
+            max_word_length: 8,
+        };
+
+        assert_eq!(ngrams.hashes("book").map(|hashes| hashes.len()), Some(2));
+        assert_eq!(ngrams.hashes("aaaa").map(|hashes| hashes.len()), Some(1));
+        assert_eq!(ngrams.hashes("ab"), None);
+        assert_eq!(ngrams.hashes("notebooks"), None);
+    }
+
+    #[test]
+    fn it_matches_substring_ngrams() {
+        let ngrams = LexerNGrams {
+            size: 3,
+            max_word_length: 24,
+        };
+
+        let (word_hashes, substring_hashes) = (
+            ngrams.hashes("notebook").unwrap(),
+            ngrams.hashes("book").unwrap(),
+        );
+
+        assert!(substring_hashes
+            .iter()
+            .all(|hash| word_hashes.contains(hash)));
+        assert!(!word_hashes.contains(&StoreTermHash::from("boo")));
+    }
+
+    #[test]
+    fn it_builds_ngrams_from_config() {
+        let config_enabled =
+            toml::from_str::<ConfigCollection>("ngrams = true\nngram_size = 3").unwrap();
+
+        assert_eq!(
+            LexerNGrams::from_config(&config_enabled)
+                .and_then(|ngrams| ngrams.hashes("book"))
+                .map(|hashes| hashes.len()),
+            Some(2)
+        );
+
+        assert!(LexerNGrams::from_config(
+            &toml::from_str::<ConfigCollection>("ngrams = false").unwrap()
+        )
+        .is_none());
+        assert!(LexerNGrams::from_config(
+            &toml::from_str::<ConfigCollection>("ngrams = true\nngram_size = 0").unwrap()
+        )
+        .is_none());
+    }
+}
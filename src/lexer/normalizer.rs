@@ -0,0 +1,182 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::options::{ConfigCollection, ConfigCollectionNormalizeForm};
+
+pub struct LexerNormalizer {
+    form: ConfigCollectionNormalizeForm,
+    fold_case: bool,
+    strip_diacritics: bool,
+    numbers: bool,
+}
+
+const NUMBER_GROUP_SEPARATORS: [char; 3] = [',', '_', '\''];
+const NUMBER_DECIMAL_SEPARATOR: char = '.';
+
+impl LexerNormalizer {
+    pub fn from_config(collection_config: Option<&ConfigCollection>) -> LexerNormalizer {
+        match collection_config {
+            Some(collection_config) => LexerNormalizer {
+                form: collection_config.normalize_form,
+                fold_case: collection_config.fold_case,
+                strip_diacritics: collection_config.strip_diacritics,
+                numbers: collection_config.normalize_numbers,
+            },
+            None => LexerNormalizer {
+                form: ConfigCollectionNormalizeForm::None,
+                fold_case: false,
+                strip_diacritics: false,
+                numbers: false,
+            },
+        }
+    }
+
+    pub fn normalize(&self, word: &str) -> String {
+        // Apply normalization form first (eg. NFKC turns ligatures and full-width characters \
+        //   into their regular equivalents)
+        let word = Self::compose(word, self.form);
+
+        // Lower-case or fold word case
+        // Notice: case folding goes beyond lower-casing, eg. 'ß' gets folded to 'ss'
+        let word = if self.fold_case {
+            caseless::default_case_fold_str(&word)
+        } else {
+            word.to_lowercase()
+        };
+
+        // Normalize numbers? (eg. '1,000.50' becomes '1000.5')
+        if self.numbers {
+            Self::normalize_number(word)
+        } else {
+            word
+        }
+    }
+
+    pub fn fold(&self, word: String) -> String {
+        // Strip diacritics? (decompose word, drop all combining marks and recompose word)
+        // Notice: this is done on the very last step, as stop-words and stemmers expect words \
+        //   to hold their diacritics.
+        if self.strip_diacritics {
+            let word_stripped: String = word.nfd().filter(|c| !is_combining_mark(*c)).collect();
+
+            match self.form {
+                ConfigCollectionNormalizeForm::None => word_stripped.nfc().collect(),
+                form => Self::compose(&word_stripped, form),
+            }
+        } else {
+            word
+        }
+    }
+
+    fn normalize_number(word: String) -> String {
+        // Only plain numbers get normalized, ie. digits with optional thousands separators and \
+        //   an optional decimal part (versions such as '1.4.0' are left as-is, as well as \
+        //   decimal commas such as '1,5', as they cannot be told apart from thousands separators)
+        let mut parts = word.splitn(2, NUMBER_DECIMAL_SEPARATOR);
+
+        let (integer, fraction) = match (parts.next(), parts.next()) {
+            (Some(integer), fraction) => (integer, fraction),
+            _ => return word,
+        };
+
+        let groups: Vec<&str> = integer.split(&NUMBER_GROUP_SEPARATORS[..]).collect();
+
+        let is_number = groups.iter().enumerate().all(|(index, group)| {
+            let is_digits = !group.is_empty() && group.bytes().all(|byte| byte.is_ascii_digit());
+
+            // Thousands separators must separate groups of 3 digits (eg. '1,000,000')
+            is_digits && (groups.len() == 1 || index == 0 && group.len() <= 3 || group.len() == 3)
+        }) && fraction
+            .map(|fraction| {
+                !fraction.is_empty() && fraction.bytes().all(|byte| byte.is_ascii_digit())
+            })
+            .unwrap_or(true);
+
+        if !is_number {
+            return word;
+        }
+
+        // Strip thousands separators, leading zeros and trailing decimal zeros
+        let integer = groups.concat();
+        let integer = match integer.trim_start_matches('0') {
+            "" => "0",
+            integer => integer,
+        };
+
+        match fraction.map(|fraction| fraction.trim_end_matches('0')) {
+            Some(fraction) if !fraction.is_empty() => {
+                format!("{}{}{}", integer, NUMBER_DECIMAL_SEPARATOR, fraction)
+            }
+            _ => integer.to_string(),
+        }
+    }
+
+    fn compose(word: &str, form: ConfigCollectionNormalizeForm) -> String {
+        match form {
+            ConfigCollectionNormalizeForm::None => word.to_owned(),
+            ConfigCollectionNormalizeForm::Nfc => word.nfc().collect(),
+            ConfigCollectionNormalizeForm::Nfd => word.nfd().collect(),
+            ConfigCollectionNormalizeForm::Nfkc => word.nfkc().collect(),
+            ConfigCollectionNormalizeForm::Nfkd => word.nfkd().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_normalizes_words_default() {
+        let normalizer = LexerNormalizer::from_config(None);
+
+        assert_eq!(normalizer.normalize("Café"), "café");
+        assert_eq!(normalizer.fold("café".to_string()), "café");
+    }
+
+    #[test]
+    fn it_normalizes_words_compatibility() {
+        let normalizer = LexerNormalizer {
+            form: ConfigCollectionNormalizeForm::Nfkc,
+            fold_case: true,
+            strip_diacritics: true,
+            numbers: false,
+        };
+
+        assert_eq!(normalizer.normalize("ﬁnance"), "finance");
+        assert_eq!(normalizer.normalize("Ｓｏｎｉｃ"), "sonic");
+        assert_eq!(normalizer.normalize("Straße"), "strasse");
+        assert_eq!(normalizer.fold(normalizer.normalize("Café")), "cafe");
+        assert_eq!(
+            normalizer.fold(normalizer.normalize("Ελληνικά")),
+            "ελληνικα"
+        );
+        assert_eq!(normalizer.fold(normalizer.normalize("한국어")), "한국어");
+    }
+
+    #[test]
+    fn it_normalizes_numbers() {
+        let normalizer = LexerNormalizer {
+            form: ConfigCollectionNormalizeForm::None,
+            fold_case: false,
+            strip_diacritics: false,
+            numbers: true,
+        };
+
+        assert_eq!(normalizer.normalize("1,000,000"), "1000000");
+        assert_eq!(normalizer.normalize("007"), "7");
+        assert_eq!(normalizer.normalize("000"), "0");
+        assert_eq!(normalizer.normalize("3.50"), "3.5");
+        assert_eq!(normalizer.normalize("1_000.00"), "1000");
+        assert_eq!(normalizer.normalize("1,5"), "1,5");
+        assert_eq!(normalizer.normalize("1.4.0"), "1.4.0");
+        assert_eq!(normalizer.normalize("12,34,567"), "12,34,567");
+        assert_eq!(normalizer.normalize("v2"), "v2");
+    }
+}
@@ -0,0 +1,138 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hashbrown::HashMap;
+use regex::Regex;
+
+use super::tokenizer::{LexerTokenizer, LexerTokenizerWords};
+use crate::APP_CONF;
+
+pub struct LexerPatterns {
+    regex: Regex,
+    fragments: bool,
+}
+
+lazy_static! {
+    static ref PATTERNS: HashMap<String, LexerPatterns> = make_patterns();
+}
+
+fn make_patterns() -> HashMap<String, LexerPatterns> {
+    let mut patterns = HashMap::new();
+
+    for (collection, collection_config) in APP_CONF.collection.iter() {
+        if !collection_config.token_patterns.is_empty() {
+            // Notice: patterns are validated when reading configuration
+            if let Some(collection_patterns) = LexerPatterns::build(
+                &collection_config.token_patterns,
+                collection_config.token_pattern_fragments,
+            ) {
+                patterns.insert(collection.to_owned(), collection_patterns);
+            }
+        }
+    }
+
+    patterns
+}
+
+impl LexerPatterns {
+    pub fn from(collection: &str) -> Option<&'static LexerPatterns> {
+        PATTERNS.get(collection)
+    }
+
+    pub fn tokenize<'a>(
+        &self,
+        text: &'a str,
+        tokenizer: &'static dyn LexerTokenizer,
+    ) -> LexerTokenizerWords<'a> {
+        let (mut words, mut last_end) = (Vec::new(), 0);
+
+        // Extract text matching patterns as whole words, and tokenize text in-between matches \
+        //   as usual (text matching patterns can also be tokenized into fragment words)
+        for found in self.regex.find_iter(text) {
+            if found.start() == found.end() {
+                continue;
+            }
+
+            words.extend(tokenizer.tokenize(&text[last_end..found.start()]));
+            words.push(found.as_str());
+
+            if self.fragments {
+                words.extend(
+                    tokenizer
+                        .tokenize(found.as_str())
+                        .filter(|fragment| *fragment != found.as_str()),
+                );
+            }
+
+            last_end = found.end();
+        }
+
+        words.extend(tokenizer.tokenize(&text[last_end..]));
+
+        Box::new(words.into_iter())
+    }
+
+    fn build(patterns: &[String], fragments: bool) -> Option<LexerPatterns> {
+        // Combine all patterns into a single regex (patterns are tried in order, ie. the first \
+        //   pattern that matches at a position wins)
+        let combined = patterns
+            .iter()
+            .map(|pattern| format!("(?:{})", pattern))
+            .collect::<Vec<String>>()
+            .join("|");
+
+        match Regex::new(&combined) {
+            Ok(regex) => Some(LexerPatterns { regex, fragments }),
+            Err(err) => {
+                error!("could not compile token patterns: {}", err);
+
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenizer::LexerTokenizerBuilder;
+
+    #[test]
+    fn it_extracts_pattern_words() {
+        let patterns = LexerPatterns::build(
+            &[
+                r"[\w.+-]+@[\w-]+\.[\w.-]+".to_string(),
+                r"\bv\d+(?:\.\d+)+\b".to_string(),
+            ],
+            false,
+        )
+        .unwrap();
+
+        let tokenizer = LexerTokenizerBuilder::from_config(None, None);
+
+        assert_eq!(
+            patterns
+                .tokenize("Mail john.doe@acme.com about v1.4.0 now", tokenizer)
+                .collect::<Vec<&str>>(),
+            vec!["Mail", "john.doe@acme.com", "about", "v1.4.0", "now"]
+        );
+    }
+
+    #[test]
+    fn it_extracts_pattern_words_with_fragments() {
+        let patterns =
+            LexerPatterns::build(&[r"\b[A-Z]{3}-\d{3}-[A-Z]\b".to_string()], true).unwrap();
+
+        let tokenizer = LexerTokenizerBuilder::from_config(None, None);
+
+        assert_eq!(
+            patterns
+                .tokenize("Order ABC-123-X shipped", tokenizer)
+                .collect::<Vec<&str>>(),
+            vec!["Order", "ABC-123-X", "ABC", "123", "X", "shipped"]
+        );
+    }
+}
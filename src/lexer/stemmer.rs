@@ -0,0 +1,97 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use rust_stemmers::{Algorithm, Stemmer};
+use whatlang::Lang;
+
+use crate::config::options::ConfigCollection;
+
+pub struct LexerStemmer(Stemmer);
+
+impl LexerStemmer {
+    pub fn from_config(
+        collection_config: &ConfigCollection,
+        locale: Option<Lang>,
+    ) -> Option<LexerStemmer> {
+        // Stemming must be explicitly enabled for the collection, as stemmed terms get stored \
+        //   in the index (ie. toggling it requires the collection to be re-indexed)
+        if collection_config.stemming {
+            locale
+                .and_then(Self::algorithm)
+                .map(|algorithm| LexerStemmer(Stemmer::create(algorithm)))
+        } else {
+            None
+        }
+    }
+
+    pub fn stem(&self, word: &str) -> String {
+        self.0.stem(word).into_owned()
+    }
+
+    fn algorithm(locale: Lang) -> Option<Algorithm> {
+        // Notice: only languages that have both a Snowball stemmer and a stop-words list are \
+        //   mapped there
+        match locale {
+            Lang::Ara => Some(Algorithm::Arabic),
+            Lang::Dan => Some(Algorithm::Danish),
+            Lang::Nld => Some(Algorithm::Dutch),
+            Lang::Eng => Some(Algorithm::English),
+            Lang::Fin => Some(Algorithm::Finnish),
+            Lang::Fra => Some(Algorithm::French),
+            Lang::Deu => Some(Algorithm::German),
+            Lang::Ell => Some(Algorithm::Greek),
+            Lang::Hun => Some(Algorithm::Hungarian),
+            Lang::Ita => Some(Algorithm::Italian),
+            Lang::Nob => Some(Algorithm::Norwegian),
+            Lang::Por => Some(Algorithm::Portuguese),
+            Lang::Ron => Some(Algorithm::Romanian),
+            Lang::Rus => Some(Algorithm::Russian),
+            Lang::Spa => Some(Algorithm::Spanish),
+            Lang::Swe => Some(Algorithm::Swedish),
+            Lang::Tam => Some(Algorithm::Tamil),
+            Lang::Tur => Some(Algorithm::Turkish),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stems_words() {
+        let stemmer_eng = LexerStemmer(Stemmer::create(Algorithm::English));
+        let stemmer_fra = LexerStemmer(Stemmer::create(Algorithm::French));
+
+        assert_eq!(stemmer_eng.stem("running"), "run");
+        assert_eq!(stemmer_eng.stem("runs"), "run");
+        assert_eq!(stemmer_fra.stem("chiens"), "chien");
+    }
+
+    #[test]
+    fn it_maps_algorithms() {
+        assert_eq!(LexerStemmer::algorithm(Lang::Eng), Some(Algorithm::English));
+        assert_eq!(LexerStemmer::algorithm(Lang::Jpn), None);
+    }
+
+    #[test]
+    fn it_builds_stemmer_from_config() {
+        let (config_enabled, config_disabled) = (
+            toml::from_str::<ConfigCollection>("stemming = true").unwrap(),
+            toml::from_str::<ConfigCollection>("stemming = false").unwrap(),
+        );
+
+        assert_eq!(
+            LexerStemmer::from_config(&config_enabled, Some(Lang::Eng))
+                .map(|stemmer| stemmer.stem("running")),
+            Some("run".to_string())
+        );
+        assert!(LexerStemmer::from_config(&config_enabled, Some(Lang::Jpn)).is_none());
+        assert!(LexerStemmer::from_config(&config_enabled, None).is_none());
+        assert!(LexerStemmer::from_config(&config_disabled, Some(Lang::Eng)).is_none());
+    }
+}
@@ -4,13 +4,26 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use hashbrown::HashSet;
+use hashbrown::{HashMap, HashSet};
 use whatlang::{Lang, Script};
 
+use crate::config::options::ConfigCollectionStopWords;
 use crate::stopwords::*;
+use crate::APP_CONF;
 
 pub struct LexerStopWord;
 
+pub struct LexerStopWordOverrides {
+    disabled: bool,
+    add: HashSet<String>,
+    remove: HashSet<String>,
+}
+
+lazy_static! {
+    static ref STOPWORDS_OVERRIDES: HashMap<String, HashMap<Lang, LexerStopWordOverrides>> =
+        make_overrides();
+}
+
 // Recursion group #1 (10 items)
 lazy_static! {
     static ref STOPWORDS_EPO: HashSet<&'static str> = make(epo::STOPWORDS_EPO);
@@ -112,9 +125,79 @@ fn make<'a>(words: &[&'a str]) -> HashSet<&'a str> {
     words.iter().copied().collect()
 }
 
+fn make_overrides() -> HashMap<String, HashMap<Lang, LexerStopWordOverrides>> {
+    let mut overrides = HashMap::new();
+
+    for (collection, collection_config) in APP_CONF.collection.iter() {
+        let mut collection_overrides = HashMap::new();
+
+        for (locale_code, stopwords_config) in collection_config.stopwords.iter() {
+            // Notice: locale codes are validated when reading configuration
+            if let Some(locale) = Lang::from_code(locale_code) {
+                collection_overrides.insert(
+                    locale,
+                    LexerStopWordOverrides::from_config(stopwords_config),
+                );
+            }
+        }
+
+        if !collection_overrides.is_empty() {
+            overrides.insert(collection.to_owned(), collection_overrides);
+        }
+    }
+
+    overrides
+}
+
+impl LexerStopWordOverrides {
+    pub fn from_config(stopwords_config: &ConfigCollectionStopWords) -> LexerStopWordOverrides {
+        LexerStopWordOverrides {
+            disabled: stopwords_config.disabled,
+            add: stopwords_config
+                .add
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect(),
+            remove: stopwords_config
+                .remove
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect(),
+        }
+    }
+}
+
 impl LexerStopWord {
-    pub fn is(word: &str, locale: Option<Lang>) -> bool {
+    pub fn overrides(
+        collection: &str,
+        locale: Option<Lang>,
+    ) -> Option<&'static LexerStopWordOverrides> {
+        locale.and_then(|locale| {
+            STOPWORDS_OVERRIDES
+                .get(collection)
+                .and_then(|collection_overrides| collection_overrides.get(&locale))
+        })
+    }
+
+    pub fn is(
+        word: &str,
+        locale: Option<Lang>,
+        overrides: Option<&LexerStopWordOverrides>,
+    ) -> bool {
         if let Some(locale) = locale {
+            // Word is overridden for collection? (removals take precedence over additions)
+            if let Some(overrides) = overrides {
+                if overrides.remove.contains(word) {
+                    return false;
+                }
+                if overrides.add.contains(word) {
+                    return true;
+                }
+                if overrides.disabled {
+                    return false;
+                }
+            }
+
             // Word is a stopword (given locale)
             if Self::lang_stopwords(locale).contains(word) {
                 return true;
@@ -335,12 +418,63 @@ mod tests {
 
     #[test]
     fn it_detects_stopwords() {
-        assert!(!LexerStopWord::is("the", None));
-        assert!(LexerStopWord::is("the", Some(Lang::Eng)));
-        assert!(!LexerStopWord::is("fox", Some(Lang::Eng)));
-        assert!(!LexerStopWord::is("bonjour", Some(Lang::Fra)));
-        assert!(LexerStopWord::is("ici", Some(Lang::Fra)));
-        assert!(LexerStopWord::is("adéu", Some(Lang::Cat)));
+        assert!(!LexerStopWord::is("the", None, None));
+        assert!(LexerStopWord::is("the", Some(Lang::Eng), None));
+        assert!(!LexerStopWord::is("fox", Some(Lang::Eng), None));
+        assert!(!LexerStopWord::is("bonjour", Some(Lang::Fra), None));
+        assert!(LexerStopWord::is("ici", Some(Lang::Fra), None));
+        assert!(LexerStopWord::is("adéu", Some(Lang::Cat), None));
+    }
+
+    #[test]
+    fn it_detects_stopwords_overridden() {
+        let overrides = LexerStopWordOverrides {
+            disabled: false,
+            add: ["fox".to_string()].iter().cloned().collect(),
+            remove: ["will".to_string()].iter().cloned().collect(),
+        };
+        let overrides_disabled = LexerStopWordOverrides {
+            disabled: true,
+            add: HashSet::new(),
+            remove: HashSet::new(),
+        };
+
+        assert!(LexerStopWord::is("the", Some(Lang::Eng), Some(&overrides)));
+        assert!(LexerStopWord::is("fox", Some(Lang::Eng), Some(&overrides)));
+        assert!(!LexerStopWord::is(
+            "will",
+            Some(Lang::Eng),
+            Some(&overrides)
+        ));
+        assert!(!LexerStopWord::is(
+            "the",
+            Some(Lang::Eng),
+            Some(&overrides_disabled)
+        ));
+    }
+
+    #[test]
+    fn it_builds_stopwords_overrides_from_config() {
+        let overrides = LexerStopWordOverrides::from_config(
+            &toml::from_str::<ConfigCollectionStopWords>("add = [\"Fox\"]\nremove = [\"will\"]")
+                .unwrap(),
+        );
+        let overrides_disabled = LexerStopWordOverrides::from_config(
+            &toml::from_str::<ConfigCollectionStopWords>("disabled = true").unwrap(),
+        );
+
+        assert!(LexerStopWord::is("fox", Some(Lang::Eng), Some(&overrides)));
+        assert!(!LexerStopWord::is(
+            "will",
+            Some(Lang::Eng),
+            Some(&overrides)
+        ));
+        assert!(LexerStopWord::is("the", Some(Lang::Eng), Some(&overrides)));
+        assert!(!LexerStopWord::is(
+            "the",
+            Some(Lang::Eng),
+            Some(&overrides_disabled)
+        ));
     }
 
     #[test]
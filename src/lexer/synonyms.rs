@@ -0,0 +1,278 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hashbrown::HashMap;
+use std::fs;
+use std::sync::RwLock;
+use unicode_segmentation::UnicodeSegmentation;
+use whatlang::Lang;
+
+use super::normalizer::LexerNormalizer;
+use super::stemmer::LexerStemmer;
+use super::transliterator::LexerTransliterator;
+use crate::config::options::ConfigCollection;
+use crate::APP_CONF;
+
+pub struct LexerSynonyms;
+
+type LexerSynonymsAlternate = Vec<String>;
+type LexerSynonymsDictionary = HashMap<String, Vec<LexerSynonymsAlternate>>;
+type LexerSynonymsDictionaries = HashMap<Option<Lang>, LexerSynonymsDictionary>;
+
+lazy_static! {
+    static ref SYNONYMS: RwLock<HashMap<String, LexerSynonymsDictionaries>> =
+        RwLock::new(HashMap::new());
+}
+
+const SYNONYMS_GROUP_SEPARATOR: char = ',';
+const SYNONYMS_COMMENT_PREFIX: char = '#';
+
+pub fn ensure_states() {
+    // Load all synonym dictionaries (a failure there is fatal, as the configuration is invalid)
+    LexerSynonyms::load().expect("cannot load synonyms");
+}
+
+impl LexerSynonyms {
+    pub fn load() -> Result<(), ()> {
+        let mut synonyms = HashMap::new();
+
+        for (collection, collection_config) in APP_CONF.collection.iter() {
+            if let Some(ref path) = collection_config.synonyms {
+                debug!(
+                    "loading synonyms for collection: {} from: {:?}",
+                    collection, path
+                );
+
+                match fs::read_to_string(path) {
+                    Ok(contents) => {
+                        let dictionaries = Self::parse(&contents, collection_config);
+
+                        info!(
+                            "loaded {} synonym words for collection: {}",
+                            dictionaries
+                                .get(&None)
+                                .map(|dictionary| dictionary.len())
+                                .unwrap_or(0),
+                            collection
+                        );
+
+                        synonyms.insert(collection.to_owned(), dictionaries);
+                    }
+                    Err(err) => {
+                        error!(
+                            "failed loading synonyms for collection: {} from: {:?}: {}",
+                            collection, path, err
+                        );
+
+                        return Err(());
+                    }
+                }
+            }
+        }
+
+        // Swap all dictionaries at once (previous dictionaries are kept if loading failed)
+        *SYNONYMS.write().unwrap() = synonyms;
+
+        Ok(())
+    }
+
+    pub fn expand(
+        collection: &str,
+        locale: Option<Lang>,
+        word: &str,
+    ) -> Option<Vec<LexerSynonymsAlternate>> {
+        // Use the dictionary for the locale words were lexed with (as words get stemmed as \
+        //   per their locale), or the dictionary for non-stemmed words otherwise
+        SYNONYMS
+            .read()
+            .unwrap()
+            .get(collection)
+            .and_then(|dictionaries| {
+                dictionaries
+                    .get(&locale)
+                    .or_else(|| dictionaries.get(&None))
+                    .and_then(|dictionary| dictionary.get(word))
+                    .cloned()
+            })
+    }
+
+    fn parse(contents: &str, collection_config: &ConfigCollection) -> LexerSynonymsDictionaries {
+        let (normalizer, transliterator) = (
+            LexerNormalizer::from_config(Some(collection_config)),
+            LexerTransliterator::from_config(collection_config),
+        );
+
+        let mut dictionaries = HashMap::new();
+
+        // Build a dictionary for each locale that words can be stemmed in (restricted to \
+        //   locales allowed for collection if any), plus a dictionary for non-stemmed words
+        let allowed_locales: Vec<Lang> = collection_config
+            .allowed_locales
+            .iter()
+            .filter_map(|locale| Lang::from_code(locale))
+            .collect();
+
+        let locales = if allowed_locales.is_empty() {
+            Lang::all()
+        } else {
+            &allowed_locales
+        };
+
+        for locale in locales {
+            if let Some(stemmer) = LexerStemmer::from_config(collection_config, Some(*locale)) {
+                dictionaries.insert(
+                    Some(*locale),
+                    Self::parse_dictionary(contents, |word| {
+                        Self::lex_word(&normalizer, Some(&stemmer), transliterator.as_ref(), word)
+                    }),
+                );
+            }
+        }
+
+        dictionaries.insert(
+            None,
+            Self::parse_dictionary(contents, |word| {
+                Self::lex_word(&normalizer, None, transliterator.as_ref(), word)
+            }),
+        );
+
+        dictionaries
+    }
+
+    fn lex_word(
+        normalizer: &LexerNormalizer,
+        stemmer: Option<&LexerStemmer>,
+        transliterator: Option<&LexerTransliterator>,
+        word: &str,
+    ) -> String {
+        // Lex dictionary word in the same way as the lexer does with query words (ie. \
+        //   normalize, stem, fold and transliterate), so that lexed query words match it
+        let word = normalizer.normalize(word);
+
+        let word = match stemmer {
+            Some(stemmer) => stemmer.stem(&word),
+            None => word,
+        };
+
+        let word = normalizer.fold(word);
+
+        transliterator
+            .and_then(|transliterator| transliterator.transliterate(&word))
+            .unwrap_or(word)
+    }
+
+    fn parse_dictionary<F: Fn(&str) -> String>(
+        contents: &str,
+        lex_word: F,
+    ) -> LexerSynonymsDictionary {
+        // Format: one group of equivalent words or phrases per line, separated with ','; eg. \
+        //   'tv, television' or 'nyc, new york'
+        // Notice: only single words are expanded, as the lexer yields words one by one; though \
+        //   phrases can be used as alternates of a single word. Alternates are kept as-is, as \
+        //   they get lexed when they are searched for.
+        let mut dictionary: LexerSynonymsDictionary = HashMap::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with(SYNONYMS_COMMENT_PREFIX) {
+                continue;
+            }
+
+            let group: Vec<LexerSynonymsAlternate> = line
+                .split(SYNONYMS_GROUP_SEPARATOR)
+                .map(|phrase| {
+                    phrase
+                        .unicode_words()
+                        .map(|word| word.to_lowercase())
+                        .collect::<LexerSynonymsAlternate>()
+                })
+                .filter(|phrase| !phrase.is_empty())
+                .collect();
+
+            for phrase in group.iter() {
+                if let [word] = phrase.as_slice() {
+                    let alternates = dictionary.entry(lex_word(word)).or_default();
+
+                    for alternate in group.iter() {
+                        if alternate != phrase && !alternates.contains(alternate) {
+                            alternates.push(alternate.to_owned());
+                        }
+                    }
+                }
+            }
+        }
+
+        dictionary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_synonyms() {
+        let dictionaries = LexerSynonyms::parse(
+            "# Comment\n\nTV, television\nnyc, New York\ntv, telly\n",
+            &toml::from_str::<ConfigCollection>("").unwrap(),
+        );
+
+        let dictionary = dictionaries.get(&None).unwrap();
+
+        assert_eq!(dictionaries.len(), 1);
+
+        assert_eq!(
+            dictionary.get("tv"),
+            Some(&vec![
+                vec!["television".to_string()],
+                vec!["telly".to_string()]
+            ])
+        );
+        assert_eq!(
+            dictionary.get("television"),
+            Some(&vec![vec!["tv".to_string()]])
+        );
+        assert_eq!(
+            dictionary.get("nyc"),
+            Some(&vec![vec!["new".to_string(), "york".to_string()]])
+        );
+        assert_eq!(dictionary.get("new"), None);
+        assert_eq!(dictionary.get("comment"), None);
+    }
+
+    #[test]
+    fn it_parses_synonyms_lexed() {
+        let dictionaries = LexerSynonyms::parse(
+            "TVs, Televisions\nCafé, coffee shop\n",
+            &toml::from_str::<ConfigCollection>(
+                "stemming = true\nstrip_diacritics = true\nallowed_locales = [\"eng\", \"jpn\"]",
+            )
+            .unwrap(),
+        );
+
+        let (dictionary_eng, dictionary_none) = (
+            dictionaries.get(&Some(Lang::Eng)).unwrap(),
+            dictionaries.get(&None).unwrap(),
+        );
+
+        // Notice: words get looked up as the lexer yields them (ie. stemmed and folded)
+        assert_eq!(dictionaries.len(), 2);
+        assert_eq!(
+            dictionary_eng.get("televis"),
+            Some(&vec![vec!["tvs".to_string()]])
+        );
+        assert_eq!(
+            dictionary_eng.get("cafe"),
+            Some(&vec![vec!["coffee".to_string(), "shop".to_string()]])
+        );
+        assert_eq!(dictionary_eng.get("televisions"), None);
+        assert_eq!(
+            dictionary_none.get("televisions"),
+            Some(&vec![vec!["tvs".to_string()]])
+        );
+    }
+}
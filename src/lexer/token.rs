@@ -5,101 +5,334 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use hashbrown::HashSet;
+use std::collections::VecDeque;
 use std::time::Instant;
-use unicode_segmentation::{UnicodeSegmentation, UnicodeWords};
+use unicode_segmentation::UnicodeSegmentation;
 use whatlang::{
-    detect as lang_detect_all, detect_lang as lang_detect, detect_script as script_detect, Lang,
+    detect as lang_detect_all, detect_lang as lang_detect, detect_script as script_detect,
+    Detector as LangDetector, Lang,
 };
 
-#[cfg(feature = "tokenizer-chinese")]
-use std::vec::IntoIter;
-
-use super::stopwords::LexerStopWord;
+use super::normalizer::LexerNormalizer;
+use super::patterns::LexerPatterns;
+use super::stemmer::LexerStemmer;
+use super::stopwords::{LexerStopWord, LexerStopWordOverrides};
+use super::tokenizer::{LexerTokenizerBuilder, LexerTokenizerWords};
+use super::transliterator::LexerTransliterator;
+use crate::config::options::ConfigCollection;
 use crate::query::types::QueryGenericLang;
 use crate::store::identifiers::{StoreTermHash, StoreTermHashed};
+use crate::APP_CONF;
 
 pub struct TokenLexerBuilder;
 
 pub struct TokenLexer<'a> {
     mode: TokenLexerMode,
     locale: Option<Lang>,
-    words: TokenLexerWords<'a>,
+    locale_method: TokenLexerLocaleMethod,
+    segments: VecDeque<TokenLexerSegment<'a>>,
+    normalizer: LexerNormalizer,
+    transliterator: Option<LexerTransliterator>,
+    transliterations_indexed: bool,
+    transliteration_pending: Option<String>,
+    stopwords_kept: bool,
     yields: HashSet<StoreTermHashed>,
+    dropped: Option<Vec<String>>,
+}
+
+struct TokenLexerSegment<'a> {
+    locale: Option<Lang>,
+    words: LexerTokenizerWords<'a>,
+    stemmer: Option<LexerStemmer>,
+    stopwords: Option<&'static LexerStopWordOverrides>,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum TokenLexerMode {
     NormalizeAndCleanup(Option<Lang>),
     NormalizeOnly,
 }
 
-enum TokenLexerWords<'a> {
-    UAX29(UnicodeWords<'a>),
-
-    #[cfg(feature = "tokenizer-chinese")]
-    JieBa(IntoIter<&'a str>),
-
-    #[cfg(feature = "tokenizer-japanese")]
-    Lindera(IntoIter<lindera_tokenizer::token::Token<'a>>),
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TokenLexerLocaleMethod {
+    Hinted,
+    Fast,
+    Slow,
+    Sentences,
+    Default,
+    Undetected,
+    Disabled,
 }
 
 const TEXT_LANG_TRUNCATE_OVER_CHARS: usize = 200;
 const TEXT_LANG_DETECT_PROCEED_OVER_CHARS: usize = 20;
 const TEXT_LANG_DETECT_NGRAM_UNDER_CHARS: usize = 60;
 
-#[cfg(feature = "tokenizer-chinese")]
-lazy_static! {
-    static ref TOKENIZER_JIEBA: jieba_rs::Jieba = jieba_rs::Jieba::new();
-}
+impl TokenLexerBuilder {
+    pub fn from<'a>(
+        mode: TokenLexerMode,
+        collection: &str,
+        text: &'a str,
+    ) -> Result<TokenLexer<'a>, ()> {
+        Self::from_within(mode, collection, text, None)
+    }
 
-#[cfg(feature = "tokenizer-japanese")]
-lazy_static! {
-    static ref TOKENIZER_LINDERA: lindera_tokenizer::tokenizer::Tokenizer =
-        lindera_tokenizer::tokenizer::Tokenizer::from_config(
-            lindera_tokenizer::tokenizer::TokenizerConfig {
-                dictionary: lindera_dictionary::DictionaryConfig {
-                    kind: Some(lindera_dictionary::DictionaryKind::UniDic),
-                    path: None
-                },
-                user_dictionary: None,
-                mode: lindera_core::mode::Mode::Normal,
-            }
+    pub fn from_query_lang<'a>(
+        lang: Option<QueryGenericLang>,
+        collection: &str,
+        text: &'a str,
+    ) -> Result<TokenLexer<'a>, ()> {
+        match lang {
+            Some(QueryGenericLang::Candidates(candidates)) => Self::from_within(
+                TokenLexerMode::NormalizeAndCleanup(None),
+                collection,
+                text,
+                Some(candidates),
+            ),
+            lang => Self::from(TokenLexerMode::from_query_lang(lang), collection, text),
+        }
+    }
+
+    pub fn normalize_literal(collection: &str, literal: &str) -> String {
+        Self::normalize_literal_within(APP_CONF.collection.get(collection), literal)
+    }
+
+    fn normalize_literal_within(
+        collection_config: Option<&ConfigCollection>,
+        literal: &str,
+    ) -> String {
+        // Normalize a literal part of a word in the same way as words get normalized by the \
+        //   lexer (it is neither stemmed nor checked for stop-words, as it is not a full word)
+        let normalizer = LexerNormalizer::from_config(collection_config);
+
+        let literal = normalizer.fold(normalizer.normalize(literal));
+
+        collection_config
+            .and_then(LexerTransliterator::from_config)
+            .and_then(|transliterator| transliterator.transliterate(&literal))
+            .unwrap_or(literal)
+    }
+
+    fn from_within<'a>(
+        mode: TokenLexerMode,
+        collection: &str,
+        text: &'a str,
+        candidates: Option<Vec<Lang>>,
+    ) -> Result<TokenLexer<'a>, ()> {
+        Self::build(
+            mode,
+            collection,
+            APP_CONF.collection.get(collection),
+            text,
+            candidates,
         )
-        .expect("unable to initialize Japanese tokenizer");
-}
+    }
 
-impl TokenLexerBuilder {
-    pub fn from(mode: TokenLexerMode, text: &str) -> Result<TokenLexer, ()> {
-        let locale = match mode {
+    fn build<'a>(
+        mode: TokenLexerMode,
+        collection: &str,
+        collection_config: Option<&ConfigCollection>,
+        text: &'a str,
+        candidates: Option<Vec<Lang>>,
+    ) -> Result<TokenLexer<'a>, ()> {
+        let (locale, locale_method, segments) = match mode {
             TokenLexerMode::NormalizeAndCleanup(None) => {
                 // Detect text language (current lexer mode asks for a cleanup)
                 debug!("detecting locale from lexer text: {}", text);
 
-                Self::detect_lang(text)
+                // Restrict detection to candidate languages (if any), otherwise to languages \
+                //   allowed for collection, and use the default language for collection if no \
+                //   language could be detected
+                let (default_locale, allowed_locales) = Self::collection_locales(collection_config);
+
+                let candidates = candidates.or(allowed_locales);
+
+                // Detect the language of each sentence? (if enabled for collection, as text \
+                //   may mix languages, eg. a support ticket half in English and half in Japanese)
+                let (locale, locale_method, segments) =
+                    if Self::detects_lang_per_sentence(collection_config) {
+                        Self::detect_lang_per_sentence(text, candidates)
+                    } else {
+                        let (locale, locale_method) =
+                            Self::detect_lang_with_method(text, candidates);
+
+                        (locale, locale_method, vec![(text, locale)])
+                    };
+
+                let segments: Vec<(&str, Option<Lang>)> = segments
+                    .into_iter()
+                    .map(|(segment_text, segment_locale)| {
+                        (segment_text, segment_locale.or(default_locale))
+                    })
+                    .collect();
+
+                match (locale, locale_method) {
+                    (None, _) if default_locale.is_some() => {
+                        debug!("using default locale: {:?} for collection", default_locale);
+
+                        (default_locale, TokenLexerLocaleMethod::Default, segments)
+                    }
+                    (locale, locale_method) => (locale, locale_method, segments),
+                }
             }
             TokenLexerMode::NormalizeAndCleanup(Some(lang)) => {
                 // Use hinted language (current lexer mode asks for a cleanup)
                 debug!("using hinted locale: {} from lexer text: {}", lang, text);
 
-                Some(lang)
+                (
+                    Some(lang),
+                    TokenLexerLocaleMethod::Hinted,
+                    vec![(text, Some(lang))],
+                )
             }
             TokenLexerMode::NormalizeOnly => {
                 debug!("not detecting locale from lexer text: {}", text);
 
                 // May be 'NormalizeOnly' mode; no need to perform a locale detection
-                None
+                (None, TokenLexerLocaleMethod::Disabled, vec![(text, None)])
             }
         };
 
-        // Build final token builder iterator
-        Ok(TokenLexer::new(mode, text, locale))
+        // Build final token builder iterator (each text segment is lexed with its own locale)
+        let mut lexer = TokenLexer::new(
+            mode,
+            locale,
+            locale_method,
+            segments
+                .into_iter()
+                .map(|(segment_text, segment_locale)| {
+                    TokenLexerSegment::from(
+                        collection,
+                        collection_config,
+                        segment_text,
+                        segment_locale,
+                    )
+                })
+                .collect(),
+            LexerNormalizer::from_config(collection_config),
+        );
+
+        // Acquire word transliterator (if enabled for collection)
+        lexer.transliterator = collection_config.and_then(LexerTransliterator::from_config);
+
+        Ok(lexer)
+    }
+
+    fn detects_lang_per_sentence(collection_config: Option<&ConfigCollection>) -> bool {
+        collection_config
+            .map(|collection_config| collection_config.detect_locale_per_sentence)
+            .unwrap_or(false)
+    }
+
+    fn detect_lang_per_sentence(
+        text: &str,
+        candidates: Option<Vec<Lang>>,
+    ) -> (
+        Option<Lang>,
+        TokenLexerLocaleMethod,
+        Vec<(&str, Option<Lang>)>,
+    ) {
+        let mut locale_method = TokenLexerLocaleMethod::Undetected;
+
+        // Split text into segments of consecutive sentences of the same language (sentences \
+        //   whose language cannot be detected, eg. short sentences, are merged with the \
+        //   previous segment, or with the next segment if they come first)
+        let mut segment_starts: Vec<(usize, Option<Lang>)> = Vec::new();
+
+        for (sentence_start, sentence) in text.split_sentence_bound_indices() {
+            let (sentence_locale, sentence_locale_method) =
+                Self::detect_lang_with_method(sentence, candidates.clone());
+
+            if sentence_locale.is_some() {
+                locale_method = sentence_locale_method;
+            }
+
+            match segment_starts.last_mut() {
+                Some((_, segment_locale))
+                    if sentence_locale.is_none() || *segment_locale == sentence_locale => {}
+                Some((_, segment_locale)) if segment_locale.is_none() => {
+                    *segment_locale = sentence_locale
+                }
+                _ => segment_starts.push((sentence_start, sentence_locale)),
+            }
+        }
+
+        let segments: Vec<(&str, Option<Lang>)> = segment_starts
+            .iter()
+            .enumerate()
+            .map(|(index, (segment_start, segment_locale))| {
+                let segment_end = segment_starts
+                    .get(index + 1)
+                    .map(|(next_segment_start, _)| *next_segment_start)
+                    .unwrap_or(text.len());
+
+                (&text[*segment_start..segment_end], *segment_locale)
+            })
+            .collect();
+
+        debug!("detected locales for lexer text sentences: {:?}", segments);
+
+        // Text holds multiple languages? (the language of its longest segment is used as \
+        //   the language of text)
+        if segments.len() > 1 {
+            locale_method = TokenLexerLocaleMethod::Sentences;
+        }
+
+        let locale = segments
+            .iter()
+            .max_by_key(|(segment_text, _)| segment_text.len())
+            .and_then(|(_, segment_locale)| *segment_locale);
+
+        (locale, locale_method, segments)
     }
 
+    #[cfg(test)]
     fn detect_lang(text: &str) -> Option<Lang> {
+        Self::detect_lang_with_method(text, None).0
+    }
+
+    fn collection_locales(
+        collection_config: Option<&ConfigCollection>,
+    ) -> (Option<Lang>, Option<Vec<Lang>>) {
+        match collection_config {
+            Some(collection_config) => {
+                let allowed_locales: Vec<Lang> = collection_config
+                    .allowed_locales
+                    .iter()
+                    .filter_map(|locale| Lang::from_code(locale))
+                    .collect();
+
+                (
+                    collection_config
+                        .default_locale
+                        .as_ref()
+                        .and_then(|locale| Lang::from_code(locale)),
+                    if !allowed_locales.is_empty() {
+                        Some(allowed_locales)
+                    } else {
+                        None
+                    },
+                )
+            }
+            None => (None, None),
+        }
+    }
+
+    fn detect_lang_with_method(
+        text: &str,
+        candidates: Option<Vec<Lang>>,
+    ) -> (Option<Lang>, TokenLexerLocaleMethod) {
+        // Single candidate language? (there is no need to detect anything)
+        if let Some(ref candidates) = candidates {
+            if candidates.len() == 1 {
+                return (candidates.first().copied(), TokenLexerLocaleMethod::Hinted);
+            }
+        }
+
         // Detect only if text is long-enough to allow the text locale detection system to \
         //   function properly
         if text.len() < TEXT_LANG_DETECT_PROCEED_OVER_CHARS {
-            return None;
+            return (None, TokenLexerLocaleMethod::Undetected);
         }
 
         // Truncate text if necessary, as to avoid the ngram or stopwords detector to be \
@@ -131,6 +364,15 @@ impl TokenLexerBuilder {
 
         debug!("will detect locale for lexer safe text: {}", safe_text);
 
+        // Detect the locale within candidate locales? (this uses the 'ngram' method, as the \
+        //   'stopwords' method cannot be restricted to candidate locales)
+        if let Some(candidates) = candidates {
+            return (
+                Self::detect_lang_within(safe_text, candidates),
+                TokenLexerLocaleMethod::Slow,
+            );
+        }
+
         // Attempt to detect the locale from text using an hybrid method that maximizes both \
         //   accuracy and performance.
         // Notice: as the 'ngram' method is almost 10x slower than the 'stopwords' method, we \
@@ -146,17 +388,45 @@ impl TokenLexerBuilder {
                 TEXT_LANG_DETECT_NGRAM_UNDER_CHARS
             );
 
-            Self::detect_lang_slow(safe_text)
+            (
+                Self::detect_lang_slow(safe_text),
+                TokenLexerLocaleMethod::Slow,
+            )
         } else {
             debug!(
                 "lexer text is equal or longer than {} characters, using the fast method",
                 TEXT_LANG_DETECT_NGRAM_UNDER_CHARS
             );
 
-            Self::detect_lang_fast(safe_text)
+            (
+                Self::detect_lang_fast(safe_text),
+                TokenLexerLocaleMethod::Fast,
+            )
         }
     }
 
+    fn detect_lang_within(safe_text: &str, candidates: Vec<Lang>) -> Option<Lang> {
+        let ngram_start = Instant::now();
+
+        debug!(
+            "[within lexer] detecting locale from text within candidates: {:?}",
+            candidates
+        );
+
+        let locale = LangDetector::with_allowlist(candidates).detect_lang(safe_text);
+        let ngram_took = ngram_start.elapsed();
+
+        info!(
+            "[within lexer] locale detected from text: {} ({:?}; {}s + {}ms)",
+            safe_text,
+            locale,
+            ngram_took.as_secs(),
+            ngram_took.subsec_millis()
+        );
+
+        locale
+    }
+
     fn detect_lang_slow(safe_text: &str) -> Option<Lang> {
         let ngram_start = Instant::now();
 
@@ -246,27 +516,134 @@ impl TokenLexerBuilder {
 }
 
 impl<'a> TokenLexer<'a> {
-    fn new(mode: TokenLexerMode, text: &'a str, locale: Option<Lang>) -> TokenLexer<'a> {
-        // Tokenize words (depending on the locale)
-        let words = match locale {
-            #[cfg(feature = "tokenizer-chinese")]
-            Some(Lang::Cmn) => TokenLexerWords::JieBa(TOKENIZER_JIEBA.cut(text, false).into_iter()),
-            #[cfg(feature = "tokenizer-japanese")]
-            Some(Lang::Jpn) => match TOKENIZER_LINDERA.tokenize(text) {
-                Ok(tokens) => TokenLexerWords::Lindera(tokens.into_iter()),
-                Err(e) => {
-                    warn!("unable to tokenize via lindera, falling back to the built-in tokenizer: {}", e);
-                    TokenLexerWords::UAX29(text.unicode_words())
-                }
-            },
-            _ => TokenLexerWords::UAX29(text.unicode_words()),
-        };
+    pub fn locale(&self) -> Option<Lang> {
+        self.locale
+    }
+
+    pub fn locale_method(&self) -> TokenLexerLocaleMethod {
+        self.locale_method
+    }
+
+    pub fn trace_dropped(&mut self) {
+        // Start keeping track of dropped stop-words (this is used for debugging purposes)
+        self.dropped = Some(Vec::new());
+    }
+
+    pub fn dropped(&self) -> Option<&Vec<String>> {
+        self.dropped.as_ref()
+    }
+
+    pub fn index_transliterations(&mut self) {
+        // Yield transliterated words along with original words, instead of transliterated \
+        //   words only (this is used when indexing text, so that text can be searched from \
+        //   either script)
+        self.transliterations_indexed = true;
+    }
 
+    pub fn keep_stopwords(&mut self) {
+        // Yield stop-words instead of dropping them, while still stemming words as per the \
+        //   lexer locale (this is used when popping text, so that words are popped as they \
+        //   were pushed, whatever stop-words overrides were at push time)
+        self.stopwords_kept = true;
+    }
+
+    pub fn resolved_mode(&self) -> TokenLexerMode {
+        // Mode that lexes other texts in the same way as this lexer (ie. with the same locale)
+        match self.mode {
+            TokenLexerMode::NormalizeAndCleanup(_) => {
+                TokenLexerMode::NormalizeAndCleanup(self.locale)
+            }
+            TokenLexerMode::NormalizeOnly => TokenLexerMode::NormalizeOnly,
+        }
+    }
+
+    fn new(
+        mode: TokenLexerMode,
+        locale: Option<Lang>,
+        locale_method: TokenLexerLocaleMethod,
+        segments: VecDeque<TokenLexerSegment<'a>>,
+        normalizer: LexerNormalizer,
+    ) -> TokenLexer<'a> {
         TokenLexer {
             mode,
             locale,
-            words,
+            locale_method,
+            segments,
+            normalizer,
+            transliterator: None,
+            transliterations_indexed: false,
+            transliteration_pending: None,
+            stopwords_kept: false,
             yields: HashSet::new(),
+            dropped: None,
+        }
+    }
+
+    fn yield_word(
+        yields: &mut HashSet<StoreTermHashed>,
+        word: String,
+    ) -> Option<(String, StoreTermHashed)> {
+        // Hash the term (this is used by all iterator consumers, as well as internally \
+        //   in the iterator to keep track of already-yielded words in a space-optimized \
+        //   manner, ie. by using 32-bit unsigned integer hashes)
+        let term_hash = StoreTermHash::from(&word);
+
+        // Check if word was not already yielded? (we return unique words)
+        if !yields.contains(&term_hash) {
+            debug!("lexer yielded word: {}", word);
+
+            yields.insert(term_hash);
+
+            Some((word, term_hash))
+        } else {
+            debug!(
+                "lexer did not yield word: {} because: word already yielded",
+                word
+            );
+
+            None
+        }
+    }
+}
+
+impl<'a> TokenLexerSegment<'a> {
+    fn from(
+        collection: &str,
+        collection_config: Option<&ConfigCollection>,
+        text: &'a str,
+        locale: Option<Lang>,
+    ) -> TokenLexerSegment<'a> {
+        // Tokenize words (text matching token patterns configured for collection is extracted \
+        //   as whole words first, if any)
+        let tokenizer = LexerTokenizerBuilder::from_config(collection_config, locale);
+
+        let words = match LexerPatterns::from(collection) {
+            Some(patterns) => patterns.tokenize(text, tokenizer),
+            None => tokenizer.tokenize(text),
+        };
+
+        // Acquire word stemmer and stop-words overrides (stemmer is used if enabled for \
+        //   collection, and if locale has a stemmer)
+        TokenLexerSegment {
+            locale,
+            words,
+            stemmer: collection_config
+                .and_then(|collection_config| LexerStemmer::from_config(collection_config, locale)),
+            stopwords: LexerStopWord::overrides(collection, locale),
+        }
+    }
+}
+
+impl TokenLexerLocaleMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenLexerLocaleMethod::Hinted => "hinted",
+            TokenLexerLocaleMethod::Fast => "fast",
+            TokenLexerLocaleMethod::Slow => "slow",
+            TokenLexerLocaleMethod::Sentences => "sentences",
+            TokenLexerLocaleMethod::Default => "default",
+            TokenLexerLocaleMethod::Undetected => "undetected",
+            TokenLexerLocaleMethod::Disabled => "disabled",
         }
     }
 }
@@ -278,6 +655,11 @@ impl TokenLexerMode {
                 // Cleanup with provided language
                 TokenLexerMode::NormalizeAndCleanup(Some(lang))
             }
+            Some(QueryGenericLang::Candidates(_)) => {
+                // Auto-detect language within candidate languages and cleanup (candidate \
+                //   languages are passed to the lexer builder)
+                TokenLexerMode::NormalizeAndCleanup(None)
+            }
             Some(QueryGenericLang::Disabled) => {
                 // Normalize only (language purposefully set to 'none')
                 TokenLexerMode::NormalizeOnly
@@ -295,68 +677,88 @@ impl<'a> Iterator for TokenLexer<'a> {
 
     // Guarantees provided by the lexer on the output: \
     //   - Text is split per-word in a script-aware way \
-    //   - Words are normalized (ie. lower-case) \
+    //   - Words are normalized (ie. lower-case, plus collection-specific normalization) \
     //   - Gibberish words are removed (ie. words that may just be junk) \
-    //   - Stop-words are removed
+    //   - Stop-words are removed (with collection-specific overrides) \
+    //   - Words are stemmed (if enabled for the collection) \
+    //   - Words are transliterated to a romanized form (if enabled for the collection)
     fn next(&mut self) -> Option<Self::Item> {
-        for word in &mut self.words {
-            // Lower-case word
-            // Notice: unfortunately, as Rust is unicode-aware, we need to convert the str slice \
-            //   to a heap-indexed String; as lower-cased characters may change in bit size.
-            let word = word.to_lowercase();
-
-            // Check if normalized word is a stop-word? (if should normalize and cleanup)
-            if self.mode == TokenLexerMode::NormalizeOnly || !LexerStopWord::is(&word, self.locale)
-            {
-                // Hash the term (this is used by all iterator consumers, as well as internally \
-                //   in the iterator to keep track of already-yielded words in a space-optimized \
-                //   manner, ie. by using 32-bit unsigned integer hashes)
-                let term_hash = StoreTermHash::from(&word);
-
-                // Check if word was not already yielded? (we return unique words)
-                if !self.yields.contains(&term_hash) {
-                    debug!("lexer yielded word: {}", word);
-
-                    self.yields.insert(term_hash);
-
-                    return Some((word, term_hash));
+        // Yield transliterated word of previous word first? (if pending)
+        if let Some(word) = self.transliteration_pending.take() {
+            if let Some(item) = Self::yield_word(&mut self.yields, word) {
+                return Some(item);
+            }
+        }
+
+        // Lex words of each text segment in order (text holds a single segment, unless the \
+        //   language of each sentence is detected)
+        while let Some(segment) = self.segments.front_mut() {
+            for word in &mut segment.words {
+                // Normalize and lower-case word
+                // Notice: unfortunately, as Rust is unicode-aware, we need to convert the str \
+                //   slice to a heap-indexed String; as lower-cased characters may change in bit \
+                //   size.
+                let word = self.normalizer.normalize(word);
+
+                // Check if normalized word is a stop-word? (if should normalize and cleanup)
+                if self.mode == TokenLexerMode::NormalizeOnly
+                    || self.stopwords_kept
+                    || !LexerStopWord::is(&word, segment.locale, segment.stopwords)
+                {
+                    // Stem word? (stop-words are checked on the original word, as stop-words \
+                    //   lists hold non-stemmed words)
+                    let word = match segment.stemmer {
+                        Some(ref stemmer) => stemmer.stem(&word),
+                        None => word,
+                    };
+
+                    // Fold word (eg. strip diacritics, if enabled for the collection)
+                    let word = self.normalizer.fold(word);
+
+                    // Transliterate word? (if enabled for the collection; when indexing, the \
+                    //   transliterated word gets yielded right after the original word, otherwise \
+                    //   it replaces the original word, as both forms of a word get indexed)
+                    let word_transliterated = self
+                        .transliterator
+                        .as_ref()
+                        .and_then(|transliterator| transliterator.transliterate(&word));
+
+                    let (word, word_pending) = match word_transliterated {
+                        Some(word_transliterated) if self.transliterations_indexed => {
+                            (word, Some(word_transliterated))
+                        }
+                        Some(word_transliterated) => (word_transliterated, None),
+                        None => (word, None),
+                    };
+
+                    if let Some(item) = Self::yield_word(&mut self.yields, word) {
+                        self.transliteration_pending = word_pending;
+
+                        return Some(item);
+                    } else if let Some(word_pending) = word_pending {
+                        if let Some(item) = Self::yield_word(&mut self.yields, word_pending) {
+                            return Some(item);
+                        }
+                    }
                 } else {
                     debug!(
-                        "lexer did not yield word: {} because: word already yielded",
+                        "lexer did not yield word: {} because: word is a stop-word",
                         word
                     );
+
+                    if let Some(ref mut dropped) = self.dropped {
+                        dropped.push(word);
+                    }
                 }
-            } else {
-                debug!(
-                    "lexer did not yield word: {} because: word is a stop-word",
-                    word
-                );
             }
+
+            self.segments.pop_front();
         }
 
         None
     }
 }
 
-impl<'a> Iterator for TokenLexerWords<'a> {
-    type Item = &'a str;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            TokenLexerWords::UAX29(token) => token.next(),
-
-            #[cfg(feature = "tokenizer-chinese")]
-            TokenLexerWords::JieBa(token) => token.next(),
-
-            #[cfg(feature = "tokenizer-japanese")]
-            TokenLexerWords::Lindera(token) => match token.next() {
-                Some(t) => Some(t.text),
-                None => None,
-            },
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,6 +767,7 @@ mod tests {
     fn it_cleans_token_english() {
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "The quick brown fox jumps over the lazy dog!",
         )
         .unwrap();
@@ -389,6 +792,7 @@ mod tests {
     fn it_cleans_token_french() {
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "Le vif renard brun saute par dessus le chien paresseux.",
         )
         .unwrap();
@@ -419,6 +823,7 @@ mod tests {
     fn it_cleans_token_chinese_jieba() {
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "我们中出了一个叛徒",
         )
         .unwrap();
@@ -432,19 +837,20 @@ mod tests {
 
     #[cfg(not(feature = "tokenizer-chinese"))]
     #[test]
-    fn it_cleans_token_chinese_naive() {
+    fn it_cleans_token_chinese_bigrams() {
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "快狐跨懒狗快狐跨懒狗",
         )
         .unwrap();
 
         assert_eq!(token_cleaner.locale, Some(Lang::Cmn));
-        assert_eq!(token_cleaner.next(), Some(("快".to_string(), 126546256)));
-        assert_eq!(token_cleaner.next(), Some(("狐".to_string(), 2879689662)));
-        assert_eq!(token_cleaner.next(), Some(("跨".to_string(), 2913342670)));
-        assert_eq!(token_cleaner.next(), Some(("懒".to_string(), 3199935961)));
-        assert_eq!(token_cleaner.next(), Some(("狗".to_string(), 3360772096)));
+        assert_eq!(token_cleaner.next(), Some(("快狐".to_string(), 3086028522)));
+        assert_eq!(token_cleaner.next(), Some(("狐跨".to_string(), 1839665576)));
+        assert_eq!(token_cleaner.next(), Some(("跨懒".to_string(), 1604465269)));
+        assert_eq!(token_cleaner.next(), Some(("懒狗".to_string(), 170232703)));
+        assert_eq!(token_cleaner.next(), Some(("狗快".to_string(), 2064519558)));
         assert_eq!(token_cleaner.next(), None);
     }
 
@@ -453,6 +859,7 @@ mod tests {
     fn it_cleans_token_japanese_lindera() {
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "関西国際空港限定トートバッグ",
         )
         .unwrap();
@@ -472,19 +879,27 @@ mod tests {
         );
         assert_eq!(token_cleaner.next(), None);
 
-        let token_cleaner =
-            TokenLexerBuilder::from(TokenLexerMode::NormalizeAndCleanup(None), "𠮷野家").unwrap();
+        let token_cleaner = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            "𠮷野家",
+        )
+        .unwrap();
 
         assert_eq!(token_cleaner.locale, None);
 
-        let token_cleaner =
-            TokenLexerBuilder::from(TokenLexerMode::NormalizeAndCleanup(None), "ヱビスビール")
-                .unwrap();
+        let token_cleaner = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            "ヱビスビール",
+        )
+        .unwrap();
 
         assert_eq!(token_cleaner.locale, None);
 
         let mut token_cleaner = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
             "𠮷野家でヱビスビールを飲んだ",
         )
         .unwrap();
@@ -506,9 +921,12 @@ mod tests {
 
     #[test]
     fn it_cleans_token_emojis() {
-        let mut token_cleaner =
-            TokenLexerBuilder::from(TokenLexerMode::NormalizeAndCleanup(None), "🚀 🙋‍♂️🙋‍♂️🙋‍♂️")
-                .unwrap();
+        let mut token_cleaner = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            "🚀 🙋‍♂️🙋‍♂️🙋‍♂️",
+        )
+        .unwrap();
 
         assert_eq!(token_cleaner.locale, None);
         assert_eq!(token_cleaner.next(), None);
@@ -518,11 +936,13 @@ mod tests {
     fn it_cleans_token_lang_hinted() {
         let mut token_cleaner_right = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(Some(Lang::Eng)),
+            "c:test:lexer",
             "This will be cleaned properly, as English was hinted rightfully so.",
         )
         .unwrap();
         let mut token_cleaner_wrong = TokenLexerBuilder::from(
             TokenLexerMode::NormalizeAndCleanup(Some(Lang::Fra)),
+            "c:test:lexer",
             "This will not be cleaned properly, as French was hinted but this is English.",
         )
         .unwrap();
@@ -540,6 +960,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_traces_token_lexer() {
+        let mut token_cleaner = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            "The quick brown fox jumps over the lazy dog!",
+        )
+        .unwrap();
+
+        token_cleaner.trace_dropped();
+
+        assert_eq!(token_cleaner.locale_method(), TokenLexerLocaleMethod::Slow);
+        assert_eq!(token_cleaner.by_ref().count(), 6);
+        assert_eq!(
+            token_cleaner.dropped(),
+            Some(&vec![
+                "the".to_string(),
+                "over".to_string(),
+                "the".to_string()
+            ])
+        );
+
+        let token_cleaner_hinted = TokenLexerBuilder::from(
+            TokenLexerMode::NormalizeAndCleanup(Some(Lang::Eng)),
+            "c:test:lexer",
+            "The quick",
+        )
+        .unwrap();
+
+        assert_eq!(
+            token_cleaner_hinted.locale_method(),
+            TokenLexerLocaleMethod::Hinted
+        );
+        assert_eq!(token_cleaner_hinted.dropped(), None);
+    }
+
     #[test]
     fn it_detects_lang_english_regular() {
         assert_eq!(
@@ -566,6 +1022,144 @@ mod tests {
     fn it_doesnt_detect_lang_english_tiny() {
         assert_eq!(TokenLexerBuilder::detect_lang("The quick"), None);
     }
+
+    #[test]
+    fn it_detects_lang_per_sentence() {
+        let (locale, locale_method, segments) = TokenLexerBuilder::detect_lang_per_sentence(
+            "The package never arrived and nobody answered my emails about it. \
+             荷物がまだ届いていませんので、至急確認していただけますでしょうか。Thanks!",
+            None,
+        );
+
+        assert_eq!(locale, Some(Lang::Jpn));
+        assert_eq!(locale_method, TokenLexerLocaleMethod::Sentences);
+        assert_eq!(
+            segments,
+            vec![
+                (
+                    "The package never arrived and nobody answered my emails about it. ",
+                    Some(Lang::Eng)
+                ),
+                (
+                    "荷物がまだ届いていませんので、至急確認していただけますでしょうか。Thanks!",
+                    Some(Lang::Jpn)
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn it_detects_lang_within_candidates() {
+        let token_cleaner_within = TokenLexerBuilder::from_query_lang(
+            Some(QueryGenericLang::Candidates(vec![Lang::Fra, Lang::Deu])),
+            "c:test:lexer",
+            "The quick brown fox jumps over the lazy dog!",
+        )
+        .unwrap();
+
+        let token_cleaner_single = TokenLexerBuilder::from_query_lang(
+            Some(QueryGenericLang::Candidates(vec![Lang::Fra])),
+            "c:test:lexer",
+            "The quick",
+        )
+        .unwrap();
+
+        assert!(
+            token_cleaner_within.locale == Some(Lang::Fra)
+                || token_cleaner_within.locale == Some(Lang::Deu)
+        );
+        assert_eq!(token_cleaner_single.locale, Some(Lang::Fra));
+        assert_eq!(
+            token_cleaner_single.locale_method,
+            TokenLexerLocaleMethod::Hinted
+        );
+    }
+
+    #[test]
+    fn it_stems_token_when_enabled() {
+        let config = toml::from_str::<ConfigCollection>("stemming = true").unwrap();
+
+        let token_cleaner = TokenLexerBuilder::build(
+            TokenLexerMode::NormalizeAndCleanup(Some(Lang::Eng)),
+            "c:test:lexer",
+            Some(&config),
+            "The runners were running marathons",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            token_cleaner.map(|(word, _)| word).collect::<Vec<String>>(),
+            vec!["runner", "run", "marathon"]
+        );
+    }
+
+    #[test]
+    fn it_pops_token_as_pushed() {
+        let config =
+            toml::from_str::<ConfigCollection>("stemming = true\ndefault_locale = \"eng\"")
+                .unwrap();
+
+        let push_terms = TokenLexerBuilder::build(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            Some(&config),
+            "The runners were running",
+            None,
+        )
+        .unwrap()
+        .map(|(_, term_hashed)| term_hashed)
+        .collect::<Vec<StoreTermHashed>>();
+
+        // Notice: popped text is too short for its locale to be detected, so the default \
+        //   locale for collection gets used
+        let mut pop_lexer = TokenLexerBuilder::build(
+            TokenLexerMode::NormalizeAndCleanup(None),
+            "c:test:lexer",
+            Some(&config),
+            "The running",
+            None,
+        )
+        .unwrap();
+
+        pop_lexer.keep_stopwords();
+
+        let pop_terms = pop_lexer.collect::<Vec<(String, StoreTermHashed)>>();
+
+        assert_eq!(
+            pop_terms
+                .iter()
+                .map(|(word, _)| word.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["the", "run"]
+        );
+        assert!(push_terms.contains(&pop_terms[1].1));
+    }
+
+    #[test]
+    fn it_normalizes_literal_as_words() {
+        let config = toml::from_str::<ConfigCollection>(
+            "stemming = true\nfold_case = true\nstrip_diacritics = true",
+        )
+        .unwrap();
+
+        assert_eq!(
+            TokenLexerBuilder::normalize_literal_within(Some(&config), "Straße"),
+            "strasse"
+        );
+        assert_eq!(
+            TokenLexerBuilder::normalize_literal_within(Some(&config), "Café"),
+            "cafe"
+        );
+        assert_eq!(
+            TokenLexerBuilder::normalize_literal_within(Some(&config), "running"),
+            "running"
+        );
+        assert_eq!(
+            TokenLexerBuilder::normalize_literal_within(None, "Café"),
+            "café"
+        );
+    }
 }
 
 #[cfg(all(feature = "benchmark", test))]
@@ -580,6 +1174,7 @@ mod benches {
         b.iter(|| {
             TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeOnly,
+                "c:test:lexer",
                 "Le vif renard brun saute par dessus le chien paresseux.",
             )
         });
@@ -590,6 +1185,7 @@ mod benches {
         b.iter(|| {
             let token_cleaner = TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeOnly,
+                "c:test:lexer",
                 "Le vif renard brun saute par dessus le chien paresseux.",
             )
             .unwrap();
@@ -603,6 +1199,7 @@ mod benches {
         b.iter(|| {
             TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(None),
+                "c:test:lexer",
                 "The quick brown fox jumps over the lazy dog!",
             )
         });
@@ -613,6 +1210,7 @@ mod benches {
         b.iter(|| {
             let token_cleaner = TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(None),
+                "c:test:lexer",
                 "The quick brown fox jumps over the lazy dog!",
             )
             .unwrap();
@@ -626,6 +1224,7 @@ mod benches {
         b.iter(|| {
             let token_cleaner = TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(None),
+                "c:test:lexer",
                 r#"Running an electrical current through water splits it into oxygen and hydrogen,
                 the latter of which can be used as a reliable, zero-emission fuel source. In the
                 past, the process of purifying water beforehand was too energy intensive for this
@@ -643,6 +1242,7 @@ mod benches {
         b.iter(|| {
             TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(None),
+                "c:test:lexer",
                 "我们中出了一个叛徒",
             )
         });
@@ -653,6 +1253,7 @@ mod benches {
         b.iter(|| {
             let token_cleaner = TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(None),
+                "c:test:lexer",
                 "我们中出了一个叛徒",
             )
             .unwrap();
@@ -666,6 +1267,7 @@ mod benches {
         b.iter(|| {
             TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(Some(Lang::Eng)),
+                "c:test:lexer",
                 "The quick brown fox jumps over the lazy dog!",
             )
         });
@@ -676,6 +1278,7 @@ mod benches {
         b.iter(|| {
             let token_cleaner = TokenLexerBuilder::from(
                 TokenLexerMode::NormalizeAndCleanup(Some(Lang::Eng)),
+                "c:test:lexer",
                 "The quick brown fox jumps over the lazy dog!",
             )
             .unwrap();
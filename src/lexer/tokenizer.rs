@@ -0,0 +1,257 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use unicode_segmentation::UnicodeSegmentation;
+use whatlang::Lang;
+
+use super::bigrams::LexerBigrams;
+use crate::config::options::{ConfigCollection, ConfigCollectionTokenizer};
+
+pub trait LexerTokenizer: Send + Sync {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a>;
+}
+
+pub struct LexerTokenizerBuilder;
+
+pub struct LexerTokenizerUax29;
+pub struct LexerTokenizerWhitespace;
+pub struct LexerTokenizerBigrams;
+
+#[cfg(feature = "tokenizer-chinese")]
+pub struct LexerTokenizerJieba;
+
+#[cfg(feature = "tokenizer-japanese")]
+pub struct LexerTokenizerLindera;
+
+pub type LexerTokenizerWords<'a> = Box<dyn Iterator<Item = &'a str> + 'a>;
+
+const TOKENIZER_DEFAULT_KEY: &str = "default";
+
+#[cfg(feature = "tokenizer-chinese")]
+lazy_static! {
+    static ref TOKENIZER_JIEBA: jieba_rs::Jieba = jieba_rs::Jieba::new();
+}
+
+#[cfg(feature = "tokenizer-japanese")]
+lazy_static! {
+    static ref TOKENIZER_LINDERA: lindera_tokenizer::tokenizer::Tokenizer =
+        lindera_tokenizer::tokenizer::Tokenizer::from_config(
+            lindera_tokenizer::tokenizer::TokenizerConfig {
+                dictionary: lindera_dictionary::DictionaryConfig {
+                    kind: Some(lindera_dictionary::DictionaryKind::UniDic),
+                    path: None
+                },
+                user_dictionary: None,
+                mode: lindera_core::mode::Mode::Normal,
+            }
+        )
+        .expect("unable to initialize Japanese tokenizer");
+}
+
+impl LexerTokenizerBuilder {
+    pub fn from_config(
+        collection_config: Option<&ConfigCollection>,
+        locale: Option<Lang>,
+    ) -> &'static dyn LexerTokenizer {
+        // Use the tokenizer configured for the collection and locale, or for the collection \
+        //   whatever the locale is, otherwise use the built-in tokenizer for the locale
+        let configured_kind = collection_config.and_then(|collection_config| {
+            locale
+                .and_then(|locale| collection_config.tokenizers.get(locale.code()))
+                .or_else(|| collection_config.tokenizers.get(TOKENIZER_DEFAULT_KEY))
+        });
+
+        configured_kind
+            .and_then(|kind| Self::from_kind(*kind))
+            .unwrap_or_else(|| Self::from_locale(collection_config, locale))
+    }
+
+    fn from_kind(kind: ConfigCollectionTokenizer) -> Option<&'static dyn LexerTokenizer> {
+        match kind {
+            ConfigCollectionTokenizer::Uax29 => Some(&LexerTokenizerUax29),
+            ConfigCollectionTokenizer::Whitespace => Some(&LexerTokenizerWhitespace),
+            ConfigCollectionTokenizer::Bigrams => Some(&LexerTokenizerBigrams),
+
+            #[cfg(feature = "tokenizer-chinese")]
+            ConfigCollectionTokenizer::Jieba => Some(&LexerTokenizerJieba),
+
+            #[cfg(feature = "tokenizer-japanese")]
+            ConfigCollectionTokenizer::Lindera => Some(&LexerTokenizerLindera),
+
+            #[allow(unreachable_patterns)]
+            _ => {
+                warn!("configured tokenizer is not compiled in, using the built-in tokenizer");
+
+                None
+            }
+        }
+    }
+
+    fn from_locale(
+        collection_config: Option<&ConfigCollection>,
+        locale: Option<Lang>,
+    ) -> &'static dyn LexerTokenizer {
+        // Notice: CJK bigrams take precedence over dictionary-based tokenizers if they are \
+        //   enabled for the collection, and are used whatever the locale is, as short texts \
+        //   (eg. search terms) are likely to get no locale detected; they are also used for \
+        //   locales that have their dictionary-based tokenizer not compiled in
+        let use_bigrams = LexerBigrams::is_enabled(collection_config, locale);
+
+        match locale {
+            #[cfg(feature = "tokenizer-chinese")]
+            Some(Lang::Cmn) if !use_bigrams => &LexerTokenizerJieba,
+
+            #[cfg(feature = "tokenizer-japanese")]
+            Some(Lang::Jpn) if !use_bigrams => &LexerTokenizerLindera,
+
+            _ => {
+                if use_bigrams {
+                    &LexerTokenizerBigrams
+                } else {
+                    &LexerTokenizerUax29
+                }
+            }
+        }
+    }
+}
+
+impl LexerTokenizer for LexerTokenizerUax29 {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a> {
+        // Split text into words, as per the Unicode word boundaries rules (ie. UAX #29)
+        Box::new(text.unicode_words())
+    }
+}
+
+impl LexerTokenizer for LexerTokenizerWhitespace {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a> {
+        // Split text on whitespaces only (ie. punctuation is kept within words, eg. for code)
+        Box::new(text.split_whitespace())
+    }
+}
+
+impl LexerTokenizer for LexerTokenizerBigrams {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a> {
+        Box::new(LexerBigrams::tokenize(text).into_iter())
+    }
+}
+
+#[cfg(feature = "tokenizer-chinese")]
+impl LexerTokenizer for LexerTokenizerJieba {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a> {
+        Box::new(TOKENIZER_JIEBA.cut(text, false).into_iter())
+    }
+}
+
+#[cfg(feature = "tokenizer-japanese")]
+impl LexerTokenizer for LexerTokenizerLindera {
+    fn tokenize<'a>(&self, text: &'a str) -> LexerTokenizerWords<'a> {
+        match TOKENIZER_LINDERA.tokenize(text) {
+            Ok(tokens) => Box::new(tokens.into_iter().map(|token| token.text)),
+            Err(err) => {
+                warn!(
+                    "unable to tokenize via lindera, falling back to the built-in tokenizer: {}",
+                    err
+                );
+
+                LexerTokenizerUax29.tokenize(text)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tokenizes_words() {
+        assert_eq!(
+            LexerTokenizerUax29
+                .tokenize("fn main() { foo_bar(); }")
+                .collect::<Vec<&str>>(),
+            vec!["fn", "main", "foo_bar"]
+        );
+        assert_eq!(
+            LexerTokenizerWhitespace
+                .tokenize("fn main() { foo_bar(); }")
+                .collect::<Vec<&str>>(),
+            vec!["fn", "main()", "{", "foo_bar();", "}"]
+        );
+    }
+
+    #[test]
+    fn it_uses_configured_tokenizer() {
+        let config = toml::from_str::<ConfigCollection>(
+            "[tokenizers]\ndefault = \"whitespace\"\neng = \"uax29\"",
+        )
+        .unwrap();
+
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(Some(&config), Some(Lang::Eng))
+                .tokenize("foo_bar();")
+                .collect::<Vec<&str>>(),
+            vec!["foo_bar"]
+        );
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(Some(&config), Some(Lang::Fra))
+                .tokenize("foo_bar();")
+                .collect::<Vec<&str>>(),
+            vec!["foo_bar();"]
+        );
+    }
+
+    #[test]
+    fn it_uses_built_in_tokenizer_when_not_configured() {
+        let config = toml::from_str::<ConfigCollection>("").unwrap();
+
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(Some(&config), Some(Lang::Eng))
+                .tokenize("hello world")
+                .collect::<Vec<&str>>(),
+            vec!["hello", "world"]
+        );
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(None, Some(Lang::Eng))
+                .tokenize("hello world")
+                .collect::<Vec<&str>>(),
+            vec!["hello", "world"]
+        );
+    }
+
+    #[cfg(all(feature = "tokenizer-chinese", not(feature = "tokenizer-japanese")))]
+    #[test]
+    fn it_uses_jieba_without_japanese_tokenizer() {
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(None, Some(Lang::Cmn))
+                .tokenize("我们中出了一个叛徒")
+                .collect::<Vec<&str>>(),
+            vec!["我们", "中", "出", "了", "一个", "叛徒"]
+        );
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(None, Some(Lang::Jpn))
+                .tokenize("東京都に住む")
+                .collect::<Vec<&str>>(),
+            vec!["東京", "京都", "都に", "に住", "住む"]
+        );
+    }
+
+    #[cfg(all(feature = "tokenizer-japanese", not(feature = "tokenizer-chinese")))]
+    #[test]
+    fn it_uses_lindera_without_chinese_tokenizer() {
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(None, Some(Lang::Jpn))
+                .tokenize("関西国際空港限定トートバッグ")
+                .collect::<Vec<&str>>(),
+            vec!["関西", "国際", "空港", "限定", "トート", "バッグ"]
+        );
+        assert_eq!(
+            LexerTokenizerBuilder::from_config(None, Some(Lang::Cmn))
+                .tokenize("快狐跨懒狗")
+                .collect::<Vec<&str>>(),
+            vec!["快狐", "狐跨", "跨懒", "懒狗"]
+        );
+    }
+}
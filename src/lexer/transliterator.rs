@@ -0,0 +1,80 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use any_ascii::any_ascii;
+
+use crate::config::options::ConfigCollection;
+
+pub struct LexerTransliterator;
+
+impl LexerTransliterator {
+    pub fn from_config(collection_config: &ConfigCollection) -> Option<LexerTransliterator> {
+        // Transliteration must be explicitly enabled for the collection, as transliterated \
+        //   words get stored in the index (ie. toggling it requires the collection to be \
+        //   re-indexed)
+        if collection_config.transliterate {
+            Some(LexerTransliterator)
+        } else {
+            None
+        }
+    }
+
+    pub fn transliterate(&self, word: &str) -> Option<String> {
+        // Word is already romanized? (nothing to transliterate)
+        if word.is_ascii() {
+            return None;
+        }
+
+        // Romanize word, and only keep alphanumeric characters from the result (as some \
+        //   romanizations contain apostrophes or capitalized syllables, eg. 'BeiJing')
+        let word_romanized: String = any_ascii(word)
+            .chars()
+            .filter(|character| character.is_ascii_alphanumeric())
+            .map(|character| character.to_ascii_lowercase())
+            .collect();
+
+        if !word_romanized.is_empty() && word_romanized != word {
+            Some(word_romanized)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_transliterates_words() {
+        let transliterator = LexerTransliterator;
+
+        assert_eq!(
+            transliterator.transliterate("юрий"),
+            Some("yuriy".to_string())
+        );
+        assert_eq!(
+            transliterator.transliterate("café"),
+            Some("cafe".to_string())
+        );
+        assert_eq!(transliterator.transliterate("sonic"), None);
+    }
+
+    #[test]
+    fn it_builds_transliterator_from_config() {
+        assert_eq!(
+            LexerTransliterator::from_config(
+                &toml::from_str::<ConfigCollection>("transliterate = true").unwrap()
+            )
+            .and_then(|transliterator| transliterator.transliterate("юрий")),
+            Some("yuriy".to_string())
+        );
+        assert!(LexerTransliterator::from_config(
+            &toml::from_str::<ConfigCollection>("transliterate = false").unwrap()
+        )
+        .is_none());
+    }
+}
@@ -36,6 +36,7 @@ use channel::statistics::ensure_states as ensure_states_channel_statistics;
 use config::logger::ConfigLogger;
 use config::options::Config;
 use config::reader::ConfigReader;
+use lexer::synonyms::ensure_states as ensure_states_lexer_synonyms;
 use store::fst::StoreFSTPool;
 use store::kv::StoreKVPool;
 use tasker::runtime::TaskerBuilder;
@@ -125,6 +126,7 @@ fn ensure_states() {
 
     // Ensure per-module states
     ensure_states_channel_statistics();
+    ensure_states_lexer_synonyms();
 }
 
 fn main() {
@@ -13,23 +13,25 @@ pub enum Query<'a> {
         StoreItem<'a>,
         QuerySearchID<'a>,
         TokenLexer<'a>,
-        QuerySearchLimit,
-        QuerySearchOffset,
+        QuerySearchOptions,
     ),
-    Suggest(
+    Explain(
         StoreItem<'a>,
         QuerySearchID<'a>,
         TokenLexer<'a>,
-        QuerySearchLimit,
+        QuerySearchOptions,
     ),
-    List(
+    Suggest(
         StoreItem<'a>,
         QuerySearchID<'a>,
+        TokenLexer<'a>,
         QuerySearchLimit,
-        QuerySearchOffset,
     ),
-    Push(StoreItem<'a>, TokenLexer<'a>),
+    Correct(StoreItem<'a>, QuerySearchID<'a>, TokenLexer<'a>),
+    List(StoreItem<'a>, QuerySearchID<'a>, QueryListOptions),
+    Push(StoreItem<'a>, TokenLexer<'a>, Vec<QueryPushSort>),
     Pop(StoreItem<'a>, TokenLexer<'a>),
+    Replace(StoreItem<'a>, TokenLexer<'a>, Vec<QueryPushSort>),
     Count(StoreItem<'a>),
     FlushC(StoreItem<'a>),
     FlushB(StoreItem<'a>),
@@ -5,7 +5,9 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use super::actions::Query;
-use super::types::{QueryGenericLang, QuerySearchLimit, QuerySearchOffset};
+use super::types::{
+    QueryGenericLang, QueryListOptions, QueryPushSort, QuerySearchLimit, QuerySearchOptions,
+};
 use crate::lexer::token::{TokenLexerBuilder, TokenLexerMode};
 use crate::store::item::StoreItemBuilder;
 
@@ -19,17 +21,31 @@ impl QueryBuilder {
         collection: &'a str,
         bucket: &'a str,
         terms: &'a str,
-        limit: QuerySearchLimit,
-        offset: QuerySearchOffset,
         lang: Option<QueryGenericLang>,
+        options: QuerySearchOptions,
     ) -> QueryBuilderResult<'a> {
         match (
             StoreItemBuilder::from_depth_2(collection, bucket),
-            TokenLexerBuilder::from(TokenLexerMode::from_query_lang(lang), terms),
+            TokenLexerBuilder::from_query_lang(lang, collection, terms),
         ) {
-            (Ok(store), Ok(text_lexed)) => {
-                Ok(Query::Search(store, query_id, text_lexed, limit, offset))
-            }
+            (Ok(store), Ok(text_lexed)) => Ok(Query::Search(store, query_id, text_lexed, options)),
+            _ => Err(()),
+        }
+    }
+
+    pub fn explain<'a>(
+        query_id: &'a str,
+        collection: &'a str,
+        bucket: &'a str,
+        terms: &'a str,
+        lang: Option<QueryGenericLang>,
+        options: QuerySearchOptions,
+    ) -> QueryBuilderResult<'a> {
+        match (
+            StoreItemBuilder::from_depth_2(collection, bucket),
+            TokenLexerBuilder::from_query_lang(lang, collection, terms),
+        ) {
+            (Ok(store), Ok(text_lexed)) => Ok(Query::Explain(store, query_id, text_lexed, options)),
             _ => Err(()),
         }
     }
@@ -43,22 +59,37 @@ impl QueryBuilder {
     ) -> QueryBuilderResult<'a> {
         match (
             StoreItemBuilder::from_depth_2(collection, bucket),
-            TokenLexerBuilder::from(TokenLexerMode::NormalizeOnly, terms),
+            TokenLexerBuilder::from(TokenLexerMode::NormalizeOnly, collection, terms),
         ) {
             (Ok(store), Ok(text_lexed)) => Ok(Query::Suggest(store, query_id, text_lexed, limit)),
             _ => Err(()),
         }
     }
 
+    pub fn correct<'a>(
+        query_id: &'a str,
+        collection: &'a str,
+        bucket: &'a str,
+        terms: &'a str,
+        lang: Option<QueryGenericLang>,
+    ) -> QueryBuilderResult<'a> {
+        match (
+            StoreItemBuilder::from_depth_2(collection, bucket),
+            TokenLexerBuilder::from_query_lang(lang, collection, terms),
+        ) {
+            (Ok(store), Ok(text_lexed)) => Ok(Query::Correct(store, query_id, text_lexed)),
+            _ => Err(()),
+        }
+    }
+
     pub fn list<'a>(
         query_id: &'a str,
         collection: &'a str,
         bucket: &'a str,
-        limit: QuerySearchLimit,
-        offset: QuerySearchOffset,
+        options: QueryListOptions,
     ) -> QueryBuilderResult<'a> {
         match StoreItemBuilder::from_depth_2(collection, bucket) {
-            Ok(store) => Ok(Query::List(store, query_id, limit, offset)),
+            Ok(store) => Ok(Query::List(store, query_id, options)),
             _ => Err(()),
         }
     }
@@ -69,12 +100,18 @@ impl QueryBuilder {
         object: &'a str,
         text: &'a str,
         lang: Option<QueryGenericLang>,
+        sorts: Vec<QueryPushSort>,
     ) -> QueryBuilderResult<'a> {
         match (
             StoreItemBuilder::from_depth_3(collection, bucket, object),
-            TokenLexerBuilder::from(TokenLexerMode::from_query_lang(lang), text),
+            TokenLexerBuilder::from_query_lang(lang, collection, text),
         ) {
-            (Ok(store), Ok(text_lexed)) => Ok(Query::Push(store, text_lexed)),
+            (Ok(store), Ok(mut text_lexed)) => {
+                // Index transliterated words along with original words (if enabled)
+                text_lexed.index_transliterations();
+
+                Ok(Query::Push(store, text_lexed, sorts))
+            }
             _ => Err(()),
         }
     }
@@ -84,12 +121,42 @@ impl QueryBuilder {
         bucket: &'a str,
         object: &'a str,
         text: &'a str,
+        lang: Option<QueryGenericLang>,
     ) -> QueryBuilderResult<'a> {
         match (
             StoreItemBuilder::from_depth_3(collection, bucket, object),
-            TokenLexerBuilder::from(TokenLexerMode::NormalizeOnly, text),
+            TokenLexerBuilder::from_query_lang(lang, collection, text),
         ) {
-            (Ok(store), Ok(text_lexed)) => Ok(Query::Pop(store, text_lexed)),
+            (Ok(store), Ok(mut text_lexed)) => {
+                // Pop words as they were pushed (ie. stemmed as per the locale), although \
+                //   stop-words are popped as well, along with transliterated words (if enabled)
+                text_lexed.keep_stopwords();
+                text_lexed.index_transliterations();
+
+                Ok(Query::Pop(store, text_lexed))
+            }
+            _ => Err(()),
+        }
+    }
+
+    pub fn replace<'a>(
+        collection: &'a str,
+        bucket: &'a str,
+        object: &'a str,
+        text: &'a str,
+        lang: Option<QueryGenericLang>,
+        sorts: Vec<QueryPushSort>,
+    ) -> QueryBuilderResult<'a> {
+        match (
+            StoreItemBuilder::from_depth_3(collection, bucket, object),
+            TokenLexerBuilder::from_query_lang(lang, collection, text),
+        ) {
+            (Ok(store), Ok(mut text_lexed)) => {
+                // Index transliterated words along with original words (if enabled)
+                text_lexed.index_transliterations();
+
+                Ok(Query::Replace(store, text_lexed, sorts))
+            }
             _ => Err(()),
         }
     }
@@ -145,11 +212,46 @@ mod tests {
 
     #[test]
     fn it_builds_search_query() {
-        assert!(
-            QueryBuilder::search("id1", "c:test:1", "b:test:1", "Michael Dake", 10, 20, None)
-                .is_ok()
-        );
-        assert!(QueryBuilder::search("id2", "c:test:1", "", "Michael Dake", 1, 0, None).is_err());
+        assert!(QueryBuilder::search(
+            "id1",
+            "c:test:1",
+            "b:test:1",
+            "Michael Dake",
+            None,
+            QuerySearchOptions::new(10, 20)
+        )
+        .is_ok());
+        assert!(QueryBuilder::search(
+            "id2",
+            "c:test:1",
+            "",
+            "Michael Dake",
+            None,
+            QuerySearchOptions::new(1, 0)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_builds_explain_query() {
+        assert!(QueryBuilder::explain(
+            "id1",
+            "c:test:1",
+            "b:test:1",
+            "Michael Dake",
+            None,
+            QuerySearchOptions::new(10, 0)
+        )
+        .is_ok());
+        assert!(QueryBuilder::explain(
+            "id2",
+            "c:test:1",
+            "",
+            "Michael Dake",
+            None,
+            QuerySearchOptions::new(10, 0)
+        )
+        .is_err());
     }
 
     #[test]
@@ -158,10 +260,19 @@ mod tests {
         assert!(QueryBuilder::suggest("id2", "c:test:2", "", "Micha", 1).is_err());
     }
 
+    #[test]
+    fn it_builds_correct_query() {
+        assert!(QueryBuilder::correct("id1", "c:test:2", "b:test:2", "Micheal", None).is_ok());
+        assert!(QueryBuilder::correct("id2", "c:test:2", "", "Micheal", None).is_err());
+    }
+
     #[test]
     fn it_builds_list_query() {
-        assert!(QueryBuilder::list("id1", "c:test:2", "b:test:2", 100, 0).is_ok());
-        assert!(QueryBuilder::list("id2", "c:test:2", "", 10, 0).is_err());
+        assert!(
+            QueryBuilder::list("id1", "c:test:2", "b:test:2", QueryListOptions::new(100, 0))
+                .is_ok()
+        );
+        assert!(QueryBuilder::list("id2", "c:test:2", "", QueryListOptions::new(10, 0)).is_err());
     }
 
     #[test]
@@ -171,19 +282,47 @@ mod tests {
             "b:test:3",
             "o:test:3",
             "My name is Michael Dake. I'm ordering in the US.",
-            None
+            None,
+            Vec::new()
         )
         .is_ok());
-        assert!(
-            QueryBuilder::push("c:test:3", "", "o:test:3", "My name is Michael Dake.", None)
-                .is_err()
-        );
+        assert!(QueryBuilder::push(
+            "c:test:3",
+            "",
+            "o:test:3",
+            "My name is Michael Dake.",
+            None,
+            Vec::new()
+        )
+        .is_err());
     }
 
     #[test]
     fn it_builds_pop_query() {
-        assert!(QueryBuilder::pop("c:test:4", "b:test:4", "o:test:4", "ordering US").is_ok());
-        assert!(QueryBuilder::pop("c:test:4", "", "o:test:4", "ordering US").is_err());
+        assert!(QueryBuilder::pop("c:test:4", "b:test:4", "o:test:4", "ordering US", None).is_ok());
+        assert!(QueryBuilder::pop("c:test:4", "", "o:test:4", "ordering US", None).is_err());
+    }
+
+    #[test]
+    fn it_builds_replace_query() {
+        assert!(QueryBuilder::replace(
+            "c:test:4",
+            "b:test:4",
+            "o:test:4",
+            "My name is Michael Dake.",
+            None,
+            Vec::new()
+        )
+        .is_ok());
+        assert!(QueryBuilder::replace(
+            "c:test:4",
+            "",
+            "o:test:4",
+            "My name is Michael Dake.",
+            None,
+            Vec::new()
+        )
+        .is_err());
     }
 
     #[test]
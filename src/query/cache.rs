@@ -0,0 +1,256 @@
+// Sonic
+//
+// Fast, lightweight and schema-less search backend
+// Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hashbrown::HashMap;
+use lru::LruCache;
+use std::sync::Mutex;
+
+use super::types::QuerySearchOptions;
+use crate::store::keyer::StoreKeyerHasher;
+use crate::APP_CONF;
+
+pub struct QueryCache;
+
+#[derive(Hash, PartialEq, Eq)]
+pub struct QueryCacheKey {
+    collection: String,
+    bucket: String,
+    locale: Option<&'static str>,
+    terms: Vec<String>,
+    options: QuerySearchOptions,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct QueryCacheGeneration(u64, u64, u64);
+
+#[derive(Default)]
+pub struct QueryCacheStatistics {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size: usize,
+}
+
+struct QueryCacheStore {
+    entries: LruCache<QueryCacheKey, QueryCacheEntry>,
+    generation: u64,
+    collection_generations: HashMap<u32, u64>,
+    bucket_generations: HashMap<(u32, u32), u64>,
+    size: usize,
+    hits: u64,
+    misses: u64,
+}
+
+struct QueryCacheEntry {
+    generation: QueryCacheGeneration,
+    results: Option<Vec<String>>,
+    size: usize,
+}
+
+// Estimated memory overhead of a cache entry, on top of its strings (used for budgeting)
+const ENTRY_SIZE_OVERHEAD: usize = 256;
+
+lazy_static! {
+    static ref QUERY_CACHE: Mutex<QueryCacheStore> = Mutex::new(QueryCacheStore {
+        entries: LruCache::unbounded(),
+        generation: 0,
+        collection_generations: HashMap::new(),
+        bucket_generations: HashMap::new(),
+        size: 0,
+        hits: 0,
+        misses: 0,
+    });
+}
+
+impl QueryCache {
+    pub fn is_enabled() -> bool {
+        APP_CONF.channel.search.query_cache_size > 0
+    }
+
+    pub fn get(key: &QueryCacheKey) -> Result<Option<Vec<String>>, QueryCacheGeneration> {
+        let mut cache = QUERY_CACHE.lock().unwrap();
+
+        let generation = cache.generation(&key.collection, &key.bucket);
+
+        // Entry found, and bucket was not mutated since then? (cache hit)
+        let cached = match cache.entries.get(key) {
+            Some(entry) if entry.generation == generation => Some(entry.results.clone()),
+            _ => None,
+        };
+
+        if let Some(results) = cached {
+            debug!(
+                "query cache hit for: {:?} on terms: {:?}",
+                key.options, key.terms
+            );
+
+            cache.hits += 1;
+
+            Ok(results)
+        } else {
+            cache.misses += 1;
+
+            // Entry is stale? (remove it straight away, as it cannot be hit anymore)
+            if let Some(stale) = cache.entries.pop(key) {
+                cache.size -= stale.size;
+            }
+
+            // Return the current generation, which will be used upon storing results
+            Err(generation)
+        }
+    }
+
+    pub fn put(
+        key: QueryCacheKey,
+        generation: QueryCacheGeneration,
+        results: &Option<Vec<String>>,
+    ) {
+        let mut cache = QUERY_CACHE.lock().unwrap();
+
+        // Bucket was mutated while results were being computed? (do not store stale results)
+        if cache.generation(&key.collection, &key.bucket) != generation {
+            return;
+        }
+
+        let size = ENTRY_SIZE_OVERHEAD
+            + key.collection.len()
+            + key.bucket.len()
+            + key.terms.iter().map(|term| term.len()).sum::<usize>()
+            + results
+                .as_ref()
+                .map(|results| results.iter().map(|result| result.len()).sum())
+                .unwrap_or(0);
+
+        let budget = APP_CONF.channel.search.query_cache_size * 1024;
+
+        // Entry would not fit in the cache at all? (do not store it)
+        if size > budget {
+            return;
+        }
+
+        if let Some(replaced) = cache.entries.put(
+            key,
+            QueryCacheEntry {
+                generation,
+                results: results.clone(),
+                size,
+            },
+        ) {
+            cache.size -= replaced.size;
+        }
+
+        cache.size += size;
+
+        // Evict least recently used entries, until the cache fits in its memory budget
+        while cache.size > budget {
+            if let Some((_, evicted)) = cache.entries.pop_lru() {
+                cache.size -= evicted.size;
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn invalidate_bucket(collection: &str, bucket: &str) {
+        Self::invalidate_bucket_hashed(
+            StoreKeyerHasher::to_compact(collection),
+            StoreKeyerHasher::to_compact(bucket),
+        )
+    }
+
+    pub fn invalidate_bucket_hashed(collection_hash: u32, bucket_hash: u32) {
+        if Self::is_enabled() {
+            debug!(
+                "invalidating query cache for collection: <{:x?}> and bucket: <{:x?}>",
+                collection_hash, bucket_hash
+            );
+
+            *QUERY_CACHE
+                .lock()
+                .unwrap()
+                .bucket_generations
+                .entry((collection_hash, bucket_hash))
+                .or_insert(0) += 1;
+        }
+    }
+
+    pub fn invalidate_collection(collection: &str) {
+        if Self::is_enabled() {
+            debug!("invalidating query cache for collection: {}", collection);
+
+            *QUERY_CACHE
+                .lock()
+                .unwrap()
+                .collection_generations
+                .entry(StoreKeyerHasher::to_compact(collection))
+                .or_insert(0) += 1;
+        }
+    }
+
+    pub fn clear() {
+        if Self::is_enabled() {
+            debug!("clearing query cache");
+
+            let mut cache = QUERY_CACHE.lock().unwrap();
+
+            // Bump global generation, as ongoing searches must not store their results
+            cache.generation += 1;
+
+            cache.entries.clear();
+            cache.size = 0;
+        }
+    }
+
+    pub fn statistics() -> QueryCacheStatistics {
+        let cache = QUERY_CACHE.lock().unwrap();
+
+        QueryCacheStatistics {
+            hits: cache.hits,
+            misses: cache.misses,
+            entries: cache.entries.len(),
+            size: cache.size,
+        }
+    }
+}
+
+impl QueryCacheKey {
+    pub fn from(
+        collection: &str,
+        bucket: &str,
+        locale: Option<&'static str>,
+        terms: Vec<String>,
+        options: QuerySearchOptions,
+    ) -> QueryCacheKey {
+        QueryCacheKey {
+            collection: collection.to_string(),
+            bucket: bucket.to_string(),
+            locale,
+            terms,
+            options,
+        }
+    }
+}
+
+impl QueryCacheStore {
+    fn generation(&self, collection: &str, bucket: &str) -> QueryCacheGeneration {
+        let (collection_hash, bucket_hash) = (
+            StoreKeyerHasher::to_compact(collection),
+            StoreKeyerHasher::to_compact(bucket),
+        );
+
+        QueryCacheGeneration(
+            self.generation,
+            self.collection_generations
+                .get(&collection_hash)
+                .copied()
+                .unwrap_or(0),
+            self.bucket_generations
+                .get(&(collection_hash, bucket_hash))
+                .copied()
+                .unwrap_or(0),
+        )
+    }
+}
@@ -6,4 +6,5 @@
 
 pub mod actions;
 pub mod builder;
+pub mod cache;
 pub mod types;
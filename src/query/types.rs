@@ -4,36 +4,420 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use fst_regex::Regex;
+use regex_syntax::escape as regex_escape;
 use whatlang::Lang;
 
+use crate::lexer::token::TokenLexerBuilder;
+
 #[derive(Debug, PartialEq)]
 pub enum QueryGenericLang {
     Enabled(Lang),
+    Candidates(Vec<Lang>),
     Disabled,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum QueryMetaData {
+    Limit(QuerySearchLimit),
+    Offset(QuerySearchOffset),
+    Lang(QueryGenericLang),
+    Order(QuerySearchOrder),
+    Range(QuerySearchRange),
+    Cursor(QuerySearchCursor),
+    With(Vec<QuerySearchWith>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ListMetaData {
+    Limit(QuerySearchLimit),
+    Offset(QuerySearchOffset),
+    Prefix(String),
+    Match(String),
+    Cursor(QuerySearchCursor),
+    With(Vec<QuerySearchWith>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PushMetaData {
+    Lang(QueryGenericLang),
+    Sort(QueryPushSort),
+    Format(QueryPushFormat),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LexMetaData {
+    Lang(QueryGenericLang),
+    Collection(String),
+    Format(QueryPushFormat),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct QuerySearchOptions {
+    pub limit: QuerySearchLimit,
+    pub offset: QuerySearchOffset,
+    pub order: Option<QuerySearchOrder>,
+    pub range: Option<QuerySearchRange>,
+    pub cursor: Option<QuerySearchCursor>,
+    pub wildcards: Vec<QuerySearchWildcard>,
+    pub with_matches: bool,
+    pub with_cursor: bool,
+    pub with_total: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryListOptions {
+    pub limit: QuerySearchLimit,
+    pub offset: QuerySearchOffset,
+    pub prefix: Option<String>,
+    pub pattern: Option<String>,
+    pub cursor: Option<QuerySearchCursor>,
+    pub with_cursor: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct QuerySearchOrder {
+    pub key: String,
+    pub direction: QuerySearchOrderDirection,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum QuerySearchOrderDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct QuerySearchRange {
+    pub key: String,
+    pub min: Option<QuerySortValue>,
+    pub max: Option<QuerySortValue>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QuerySearchWith {
+    Matches,
+    Cursor,
+    Total,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct QuerySearchCursor(Vec<u8>);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct QuerySearchWildcard {
+    pub term: String,
+    pub prefix: Option<String>,
+    pub pattern: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryPushSort {
+    pub key: String,
+    pub value: QuerySortValue,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QueryPushFormat {
+    Html,
+    Markdown,
+    Text,
+}
+
 pub type QuerySearchID<'a> = &'a str;
 pub type QuerySearchLimit = u16;
 pub type QuerySearchOffset = u32;
+pub type QuerySortValue = i64;
 
-pub type QueryMetaData = (
-    Option<QuerySearchLimit>,
-    Option<QuerySearchOffset>,
-    Option<QueryGenericLang>,
-);
+const META_VALUE_SEPARATOR: char = ',';
 
-pub type ListMetaData = (Option<QuerySearchLimit>, Option<QuerySearchOffset>);
+const WILDCARD_ANY: char = '*';
+const WILDCARD_ONE: char = '?';
+const WILDCARD_REGEX_DELIMITER: char = '/';
 
 impl QueryGenericLang {
     pub fn from_value(value: &str) -> Option<QueryGenericLang> {
+        // Format: 'none', '<locale>' or '<locale>[,<locale>]+' where <locale> ∈ ISO 639-3 \
+        //   (multiple locales restrict locale detection to these candidates)
         if value == "none" {
             Some(QueryGenericLang::Disabled)
+        } else if value.contains(META_VALUE_SEPARATOR) {
+            value
+                .split(META_VALUE_SEPARATOR)
+                .map(Lang::from_code)
+                .collect::<Option<Vec<Lang>>>()
+                .map(QueryGenericLang::Candidates)
         } else {
             Lang::from_code(value).map(QueryGenericLang::Enabled)
         }
     }
 }
 
+impl QuerySearchOptions {
+    pub fn new(limit: QuerySearchLimit, offset: QuerySearchOffset) -> QuerySearchOptions {
+        QuerySearchOptions {
+            limit,
+            offset,
+            order: None,
+            range: None,
+            cursor: None,
+            wildcards: Vec::new(),
+            with_matches: false,
+            with_cursor: false,
+            with_total: false,
+        }
+    }
+}
+
+impl QueryListOptions {
+    pub fn new(limit: QuerySearchLimit, offset: QuerySearchOffset) -> QueryListOptions {
+        QueryListOptions {
+            limit,
+            offset,
+            prefix: None,
+            pattern: None,
+            cursor: None,
+            with_cursor: false,
+        }
+    }
+}
+
+impl QuerySearchOrder {
+    pub fn from_value(value: &str) -> Option<QuerySearchOrder> {
+        // Format: '<key>,<direction>' where <direction> is either 'asc' or 'desc'
+        let mut parts = value.splitn(2, META_VALUE_SEPARATOR);
+
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(direction)) if !key.is_empty() => {
+                let direction = match direction {
+                    "asc" => QuerySearchOrderDirection::Asc,
+                    "desc" => QuerySearchOrderDirection::Desc,
+                    _ => return None,
+                };
+
+                Some(QuerySearchOrder {
+                    key: key.to_string(),
+                    direction,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl QuerySearchRange {
+    pub fn from_value(value: &str) -> Option<QuerySearchRange> {
+        // Format: '<key>,<min>,<max>' where <min> or <max> can be left empty (ie. unbounded)
+        let mut parts = value.splitn(3, META_VALUE_SEPARATOR);
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(key), Some(min), Some(max)) if !key.is_empty() => {
+                let (min, max) = (Self::parse_bound(min)?, Self::parse_bound(max)?);
+
+                // Empty ranges are rejected (they would always match nothing)
+                if let (Some(min_value), Some(max_value)) = (min, max) {
+                    if min_value > max_value {
+                        return None;
+                    }
+                }
+
+                Some(QuerySearchRange {
+                    key: key.to_string(),
+                    min,
+                    max,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, value: QuerySortValue) -> bool {
+        self.min.map(|min| value >= min).unwrap_or(true)
+            && self.max.map(|max| value <= max).unwrap_or(true)
+    }
+
+    fn parse_bound(bound: &str) -> Option<Option<QuerySortValue>> {
+        if bound.is_empty() {
+            Some(None)
+        } else {
+            bound.parse::<QuerySortValue>().ok().map(Some)
+        }
+    }
+}
+
+impl QuerySearchWith {
+    pub fn from_value(value: &str) -> Option<Vec<QuerySearchWith>> {
+        // Format: '<flag>[,<flag>]*' where <flag> ∈ {matches, cursor, total}
+        value
+            .split(META_VALUE_SEPARATOR)
+            .map(|flag| match flag {
+                "matches" => Some(QuerySearchWith::Matches),
+                "cursor" => Some(QuerySearchWith::Cursor),
+                "total" => Some(QuerySearchWith::Total),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl QuerySearchCursor {
+    pub fn from_value(value: &str) -> Option<QuerySearchCursor> {
+        // Format: '<bytes>' where <bytes> is an hexadecimal-encoded opaque value
+        if value.is_empty()
+            || value.len() % 2 != 0
+            || !value.bytes().all(|byte| byte.is_ascii_hexdigit())
+        {
+            return None;
+        }
+
+        (0..value.len())
+            .step_by(2)
+            .map(|index| {
+                value
+                    .get(index..index + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .map(QuerySearchCursor)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> QuerySearchCursor {
+        QuerySearchCursor(bytes.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn to_value(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+impl QuerySearchWildcard {
+    pub fn extract_from_text<'a>(
+        text: &'a str,
+        collection: &str,
+    ) -> Result<(String, Vec<QuerySearchWildcard>), &'a str> {
+        // Split wildcard terms from other words in text (wildcard terms must not be passed to \
+        //   the lexer, as it would split them into words on their wildcard characters)
+        let (mut words, mut wildcards) = (Vec::new(), Vec::new());
+
+        for word in text.split_whitespace() {
+            match Self::from_term(word, collection) {
+                Ok(Some(wildcard)) => {
+                    if !wildcards.contains(&wildcard) {
+                        wildcards.push(wildcard)
+                    }
+                }
+                Ok(None) => words.push(word),
+                Err(_) => return Err(word),
+            }
+        }
+
+        Ok((words.join(" "), wildcards))
+    }
+
+    pub fn from_term(term: &str, collection: &str) -> Result<Option<QuerySearchWildcard>, ()> {
+        // Format: '/<regex>/' for a regular expression term (that must match whole words), or \
+        //   '<word>' holding '*' (any characters) or '?' (any single character) wildcards
+        // Notice: a trailing '?' is not considered as a wildcard, as it is more likely to be \
+        //   a question mark (eg. 'what is sonic?')
+        if term.len() > 2
+            && term.starts_with(WILDCARD_REGEX_DELIMITER)
+            && term.ends_with(WILDCARD_REGEX_DELIMITER)
+        {
+            let pattern = &term[1..(term.len() - 1)];
+
+            // Reject invalid regular expressions early (they would otherwise fail the search)
+            if Regex::new(pattern).is_err() {
+                return Err(());
+            }
+
+            return Ok(Some(QuerySearchWildcard {
+                term: term.to_string(),
+                prefix: None,
+                pattern: pattern.to_string(),
+            }));
+        }
+
+        let term_trimmed = term.trim_end_matches(WILDCARD_ONE);
+
+        if !term_trimmed.contains(WILDCARD_ANY) && !term_trimmed.contains(WILDCARD_ONE) {
+            return Ok(None);
+        }
+
+        // Build a regular expression from wildcard term, where literal parts get normalized \
+        //   as indexed words are (eg. case-folded or transliterated, if enabled for the \
+        //   collection) and then escaped; the literal part that comes before the first \
+        //   wildcard is used as a prefix
+        let (mut term_normalized, mut prefix, mut pattern) = (String::new(), None, String::new());
+
+        for part in term_trimmed.split_inclusive([WILDCARD_ANY, WILDCARD_ONE]) {
+            let (literal, wildcard) = match part.chars().last() {
+                Some(wildcard @ WILDCARD_ANY) | Some(wildcard @ WILDCARD_ONE) => {
+                    (&part[..(part.len() - wildcard.len_utf8())], Some(wildcard))
+                }
+                _ => (part, None),
+            };
+
+            if !literal.is_empty() {
+                let literal = TokenLexerBuilder::normalize_literal(collection, literal);
+
+                if pattern.is_empty() {
+                    prefix = Some(literal.clone());
+                }
+
+                pattern.push_str(&regex_escape(&literal));
+                term_normalized.push_str(&literal);
+            }
+
+            match wildcard {
+                Some(WILDCARD_ANY) => pattern.push_str(".*"),
+                Some(_) => pattern.push('.'),
+                None => {}
+            }
+
+            term_normalized.extend(wildcard);
+        }
+
+        Ok(Some(QuerySearchWildcard {
+            term: term_normalized,
+            prefix: prefix.filter(|prefix| !prefix.is_empty()),
+            pattern,
+        }))
+    }
+}
+
+impl QueryPushSort {
+    pub fn from_value(value: &str) -> Option<QueryPushSort> {
+        // Format: '<key>,<value>' where <value> is a signed 64 bits integer
+        let mut parts = value.splitn(2, META_VALUE_SEPARATOR);
+
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) if !key.is_empty() => value
+                .parse::<QuerySortValue>()
+                .ok()
+                .map(|value| QueryPushSort {
+                    key: key.to_string(),
+                    value,
+                }),
+            _ => None,
+        }
+    }
+}
+
+impl QueryPushFormat {
+    pub fn from_value(value: &str) -> Option<QueryPushFormat> {
+        match value {
+            "html" => Some(QueryPushFormat::Html),
+            "markdown" => Some(QueryPushFormat::Markdown),
+            "text" => Some(QueryPushFormat::Text),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,6 +432,192 @@ mod tests {
             QueryGenericLang::from_value("fra"),
             Some(QueryGenericLang::Enabled(Lang::Fra))
         );
+        assert_eq!(
+            QueryGenericLang::from_value("eng,fra,deu"),
+            Some(QueryGenericLang::Candidates(vec![
+                Lang::Eng,
+                Lang::Fra,
+                Lang::Deu
+            ]))
+        );
         assert_eq!(QueryGenericLang::from_value("xxx"), None);
+        assert_eq!(QueryGenericLang::from_value("eng,none"), None);
+        assert_eq!(QueryGenericLang::from_value("eng,"), None);
+    }
+
+    #[test]
+    fn it_parses_search_order_from_value() {
+        assert_eq!(
+            QuerySearchOrder::from_value("updated_at,desc"),
+            Some(QuerySearchOrder {
+                key: "updated_at".to_string(),
+                direction: QuerySearchOrderDirection::Desc
+            })
+        );
+        assert_eq!(
+            QuerySearchOrder::from_value("price,asc"),
+            Some(QuerySearchOrder {
+                key: "price".to_string(),
+                direction: QuerySearchOrderDirection::Asc
+            })
+        );
+        assert_eq!(QuerySearchOrder::from_value("price"), None);
+        assert_eq!(QuerySearchOrder::from_value("price,up"), None);
+        assert_eq!(QuerySearchOrder::from_value(",asc"), None);
+    }
+
+    #[test]
+    fn it_parses_search_range_from_value() {
+        assert_eq!(
+            QuerySearchRange::from_value("price,100,500"),
+            Some(QuerySearchRange {
+                key: "price".to_string(),
+                min: Some(100),
+                max: Some(500)
+            })
+        );
+        assert_eq!(
+            QuerySearchRange::from_value("price,,-20"),
+            Some(QuerySearchRange {
+                key: "price".to_string(),
+                min: None,
+                max: Some(-20)
+            })
+        );
+        assert_eq!(QuerySearchRange::from_value("price,500,100"), None);
+        assert_eq!(QuerySearchRange::from_value("price,100"), None);
+        assert_eq!(QuerySearchRange::from_value("price,a,b"), None);
+    }
+
+    #[test]
+    fn it_checks_search_range_contains() {
+        let range = QuerySearchRange::from_value("price,100,").unwrap();
+
+        assert!(range.contains(100));
+        assert!(range.contains(i64::MAX));
+        assert!(!range.contains(99));
+    }
+
+    #[test]
+    fn it_parses_search_with_from_value() {
+        assert_eq!(
+            QuerySearchWith::from_value("matches"),
+            Some(vec![QuerySearchWith::Matches])
+        );
+        assert_eq!(
+            QuerySearchWith::from_value("matches,cursor"),
+            Some(vec![QuerySearchWith::Matches, QuerySearchWith::Cursor])
+        );
+        assert_eq!(
+            QuerySearchWith::from_value("total"),
+            Some(vec![QuerySearchWith::Total])
+        );
+        assert_eq!(QuerySearchWith::from_value("matches,other"), None);
+        assert_eq!(QuerySearchWith::from_value(""), None);
+    }
+
+    #[test]
+    fn it_parses_search_cursor_from_value() {
+        assert_eq!(
+            QuerySearchCursor::from_value("00ff10"),
+            Some(QuerySearchCursor::from_bytes(&[0, 255, 16]))
+        );
+        assert_eq!(
+            QuerySearchCursor::from_bytes(&[0, 255, 16]).to_value(),
+            "00ff10"
+        );
+        assert_eq!(QuerySearchCursor::from_value(""), None);
+        assert_eq!(QuerySearchCursor::from_value("0ff"), None);
+        assert_eq!(QuerySearchCursor::from_value("zz"), None);
+        assert_eq!(QuerySearchCursor::from_value("+f"), None);
+        assert_eq!(QuerySearchCursor::from_value("éa"), None);
+    }
+
+    #[test]
+    fn it_parses_search_wildcard_from_term() {
+        assert_eq!(
+            QuerySearchWildcard::from_term("Inv*ce", "c:test:types"),
+            Ok(Some(QuerySearchWildcard {
+                term: "inv*ce".to_string(),
+                prefix: Some("inv".to_string()),
+                pattern: "inv.*ce".to_string(),
+            }))
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("?.net", "c:test:types"),
+            Ok(Some(QuerySearchWildcard {
+                term: "?.net".to_string(),
+                prefix: None,
+                pattern: ".\\.net".to_string(),
+            }))
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("/colou?r/", "c:test:types"),
+            Ok(Some(QuerySearchWildcard {
+                term: "/colou?r/".to_string(),
+                prefix: None,
+                pattern: "colou?r".to_string(),
+            }))
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("/colou(r/", "c:test:types"),
+            Err(())
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("sonic?", "c:test:types"),
+            Ok(None)
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("sonic", "c:test:types"),
+            Ok(None)
+        );
+        assert_eq!(
+            QuerySearchWildcard::from_term("//", "c:test:types"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn it_extracts_search_wildcards_from_text() {
+        let (text, wildcards) = QuerySearchWildcard::extract_from_text(
+            "unpaid inv*ce  from 2019 INV*CE what?",
+            "c:test:types",
+        )
+        .unwrap();
+
+        assert_eq!(text, "unpaid from 2019 what?");
+        assert_eq!(wildcards.len(), 1);
+        assert_eq!(wildcards[0].term, "inv*ce");
+
+        assert_eq!(
+            QuerySearchWildcard::extract_from_text("unpaid /inv[/", "c:test:types"),
+            Err("/inv[/")
+        );
+    }
+
+    #[test]
+    fn it_parses_push_sort_from_value() {
+        assert_eq!(
+            QueryPushSort::from_value("updated_at,1666000000"),
+            Some(QueryPushSort {
+                key: "updated_at".to_string(),
+                value: 1666000000
+            })
+        );
+        assert_eq!(QueryPushSort::from_value("updated_at"), None);
+        assert_eq!(QueryPushSort::from_value("updated_at,now"), None);
+    }
+
+    #[test]
+    fn it_parses_push_format_from_value() {
+        assert_eq!(
+            QueryPushFormat::from_value("html"),
+            Some(QueryPushFormat::Html)
+        );
+        assert_eq!(
+            QueryPushFormat::from_value("markdown"),
+            Some(QueryPushFormat::Markdown)
+        );
+        assert_eq!(QueryPushFormat::from_value("pdf"), None);
     }
 }
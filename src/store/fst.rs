@@ -29,8 +29,10 @@ use std::time::SystemTime;
 use super::generic::{
     StoreGeneric, StoreGenericActionBuilder, StoreGenericBuilder, StoreGenericPool,
 };
+use super::identifiers::{StoreTermHash, StoreTermHashed};
 use super::keyer::StoreKeyerHasher;
 use crate::lexer::ranges::LexerRegexRange;
+use crate::query::cache::QueryCache;
 use crate::APP_CONF;
 
 pub struct StoreFSTPool;
@@ -269,6 +271,10 @@ impl StoreFSTPool {
 
                         let consolidate_counts = Self::consolidate_item(store);
 
+                        // Cached query results for this bucket may now differ (eg. alternate \
+                        //   words may now be found in the consolidated graph)
+                        QueryCache::invalidate_bucket_hashed(key.collection_hash, key.bucket_hash);
+
                         count_moved += consolidate_counts.1;
                         count_pushed += consolidate_counts.2;
                         count_popped += consolidate_counts.3;
@@ -806,8 +812,25 @@ impl StoreFST {
         self.graph.len()
     }
 
-    pub fn as_stream(&self) -> FSTStream<AlwaysMatch> {
-        self.graph.into_stream()
+    pub fn lookup_list<A: Automaton>(
+        &self,
+        automaton: A,
+        prefix: Option<&str>,
+        after_word: Option<&[u8]>,
+    ) -> FSTStream<A> {
+        let stream_builder = self.graph.search(automaton);
+
+        // Seek stream to the first word that starts with prefix, or that comes right after given \
+        //   word (in graph order), whichever comes last
+        match (prefix.map(|prefix| prefix.as_bytes()), after_word) {
+            (Some(prefix), Some(after_word)) if after_word >= prefix => {
+                stream_builder.gt(after_word)
+            }
+            (Some(prefix), _) => stream_builder.ge(prefix),
+            (None, Some(after_word)) => stream_builder.gt(after_word),
+            (None, None) => stream_builder,
+        }
+        .into_stream()
     }
 
     pub fn lookup_begins(&self, word: &str) -> Result<FSTStream<Regex>, ()> {
@@ -1128,27 +1151,52 @@ impl StoreFSTAction {
         from_word: &str,
         limit: usize,
         max_typo_factor: Option<u32>,
+    ) -> Option<Vec<String>> {
+        self.suggest_words_filtered(from_word, limit, limit, max_typo_factor, |_| true)
+    }
+
+    pub fn suggest_words_filtered<F: FnMut(&str) -> bool>(
+        &self,
+        from_word: &str,
+        limit: usize,
+        scan_maximum: usize,
+        max_typo_factor: Option<u32>,
+        mut accept: F,
     ) -> Option<Vec<String>> {
         // Word over limit? (abort, the FST does not perform well over large words)
         if Self::word_over_limit(from_word) {
             return None;
         }
 
-        let mut found_words = Vec::with_capacity(limit);
+        let (mut found_words, mut scanned) = (Vec::with_capacity(limit), 0);
 
         // Try to complete provided word
         if let Ok(stream) = self.store.lookup_begins(from_word) {
             debug!("looking up for word: {} in 'begins' fst stream", from_word);
 
-            Self::find_words_stream(stream, &mut found_words, limit);
+            Self::find_words_stream(
+                stream,
+                &mut found_words,
+                limit,
+                &mut scanned,
+                scan_maximum,
+                &mut accept,
+            );
         }
 
         // Try to fuzzy-suggest other words? (eg. correct typos)
-        if found_words.len() < limit {
+        if found_words.len() < limit && scanned < scan_maximum {
             if let Ok(stream) = self.store.lookup_typos(from_word, max_typo_factor) {
                 debug!("looking up for word: {} in 'typos' fst stream", from_word);
 
-                Self::find_words_stream(stream, &mut found_words, limit);
+                Self::find_words_stream(
+                    stream,
+                    &mut found_words,
+                    limit,
+                    &mut scanned,
+                    scan_maximum,
+                    &mut accept,
+                );
             }
         }
 
@@ -1159,22 +1207,133 @@ impl StoreFSTAction {
         }
     }
 
-    pub fn list_words(&self, limit: usize, offset: usize) -> Result<Vec<String>, ()> {
-        let stream = self.store.as_stream();
+    pub fn correct_words(&self, from_word: &str, limit: usize) -> Option<Vec<String>> {
+        // Word over limit? (abort, the FST does not perform well over large words)
+        if Self::word_over_limit(from_word) {
+            return None;
+        }
 
-        // Enumerate words from FST stream
-        match stream
-            .into_strs()
-            .map(|words| words.into_iter().skip(offset).take(limit).collect())
-        {
-            Err(err) => {
-                debug!("conversion of stream failed: {}", err.to_string());
-                Err(())
+        let (mut found_words, mut scanned) = (Vec::with_capacity(limit), 0);
+
+        // Try to fuzzy-correct provided word (ie. only look for typos, not for completions)
+        if let Ok(stream) = self.store.lookup_typos(from_word, None) {
+            debug!("looking up for word: {} in 'typos' fst stream", from_word);
+
+            Self::find_words_stream(
+                stream,
+                &mut found_words,
+                limit,
+                &mut scanned,
+                limit,
+                &mut |_| true,
+            );
+        }
+
+        if !found_words.is_empty() {
+            Some(found_words)
+        } else {
+            None
+        }
+    }
+
+    pub fn list_words(
+        &self,
+        limit: usize,
+        offset: usize,
+        prefix: Option<&str>,
+        pattern: Option<&str>,
+        after_word: Option<&[u8]>,
+    ) -> Result<Vec<String>, ()> {
+        // Filter on pattern? (the regex gets applied on the graph, so only matching words are \
+        //   streamed, instead of all words being filtered in memory)
+        if let Some(pattern) = pattern {
+            debug!("listing words in fst with pattern: {}", pattern);
+
+            match Regex::new(pattern) {
+                Ok(regex) => Self::list_words_stream(
+                    self.store.lookup_list(regex, prefix, after_word),
+                    limit,
+                    offset,
+                    prefix,
+                ),
+                Err(err) => {
+                    debug!(
+                        "could not compile list pattern: {} because: {}",
+                        pattern, err
+                    );
+
+                    Err(())
+                }
             }
-            Ok(words) => Ok(words),
+        } else {
+            Self::list_words_stream(
+                self.store.lookup_list(AlwaysMatch, prefix, after_word),
+                limit,
+                offset,
+                prefix,
+            )
         }
     }
 
+    pub fn find_words_containing<F: FnMut(&str) -> bool>(
+        &self,
+        part: &str,
+        scan_maximum: usize,
+        mut accept: F,
+    ) {
+        // Hand words that contain part to acceptor, until acceptor is done (ie. it returns \
+        //   false) or enough words got scanned
+        // Notice: as words are not indexed per substring, this scans graph words in order; the \
+        //   number of scanned words is bounded, as this would otherwise stream the whole graph.
+        let (mut stream, mut scanned) = (self.store.graph.stream(), 0);
+
+        while scanned < scan_maximum {
+            match stream.next() {
+                Some(word) => {
+                    scanned += 1;
+
+                    if let Ok(word) = str::from_utf8(word) {
+                        if word.contains(part) && !accept(word) {
+                            break;
+                        }
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn resolve_words(&self, words_hashed: &HashSet<StoreTermHashed>) -> Vec<String> {
+        // Resolve words from their hashes, in pending pushed words and then in graph words, \
+        //   until all words are resolved
+        // Notice: as words are not indexed per hash, this streams the whole graph in the worst \
+        //   case (eg. if some words are over limit, and thus never made it to the graph).
+        let mut words = Vec::new();
+
+        let pending_push = self.store.pending.push.read().unwrap();
+
+        let (mut pending_stream, mut graph_stream) =
+            (pending_push.iter(), self.store.graph.stream());
+
+        while words.len() < words_hashed.len() {
+            let word = match pending_stream.next() {
+                Some(word) => word.as_slice(),
+                None => match graph_stream.next() {
+                    Some(word) => word,
+                    None => break,
+                },
+            };
+
+            if let Ok(word) = str::from_utf8(word) {
+                if words_hashed.contains(&StoreTermHash::from(word)) {
+                    words.push(word.to_string());
+                }
+            }
+        }
+
+        words
+    }
+
     pub fn count_words(&self) -> usize {
         self.store.cardinality()
     }
@@ -1189,20 +1348,74 @@ impl StoreFSTAction {
         }
     }
 
-    fn find_words_stream<A: Automaton>(
+    fn list_words_stream<A: Automaton>(
+        mut stream: FSTStream<A>,
+        limit: usize,
+        offset: usize,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, ()> {
+        let (mut words, mut skipped) = (Vec::with_capacity(limit), 0);
+
+        // Enumerate words from FST stream
+        while let Some(word) = stream.next() {
+            // Went past words that start with prefix? Stop there (words are streamed in order)
+            if let Some(prefix) = prefix {
+                if !word.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+            }
+
+            if skipped < offset {
+                skipped += 1;
+
+                continue;
+            }
+
+            match str::from_utf8(word) {
+                Ok(word_str) => words.push(word_str.to_string()),
+                Err(err) => {
+                    debug!("conversion of stream word failed: {}", err.to_string());
+
+                    return Err(());
+                }
+            }
+
+            // Requested limit reached? Stop there.
+            if words.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(words)
+    }
+
+    fn find_words_stream<A: Automaton, F: FnMut(&str) -> bool>(
         mut stream: FSTStream<A>,
         found_words: &mut Vec<String>,
         limit: usize,
+        scanned: &mut usize,
+        scan_maximum: usize,
+        accept: &mut F,
     ) {
         while let Some(word) = stream.next() {
             if let Ok(word_str) = str::from_utf8(word) {
                 let word_string = word_str.to_string();
 
                 if !found_words.contains(&word_string) {
-                    found_words.push(word_string);
+                    *scanned += 1;
+
+                    // Only keep accepted words (all words are accepted if not filtered)
+                    if accept(word_str) {
+                        found_words.push(word_string);
+
+                        // Requested limit reached? Stop there.
+                        if found_words.len() >= limit {
+                            break;
+                        }
+                    }
 
-                    // Requested limit reached? Stop there.
-                    if found_words.len() >= limit {
+                    // Scanned too many words? Stop there (eg. most words are not accepted)
+                    if *scanned >= scan_maximum {
                         break;
                     }
                 }
@@ -10,9 +10,13 @@ use twox_hash::XxHash32;
 pub type StoreObjectIID = u32;
 pub type StoreObjectOID<'a> = &'a str;
 pub type StoreTermHashed = u32;
+pub type StoreSortKeyHashed = StoreTermHashed;
+pub type StoreSortValue = i64;
 
 pub struct StoreTermHash;
 
+pub type StoreSortKeyHash = StoreTermHash;
+
 pub enum StoreMetaKey {
     IIDIncr,
 }
@@ -53,4 +57,10 @@ mod tests {
         assert_eq!(StoreTermHash::from("hash:1"), 3637660813);
         assert_eq!(StoreTermHash::from("hash:2"), 3577985381);
     }
+
+    #[test]
+    fn it_hashes_sort_key() {
+        assert_eq!(StoreSortKeyHash::from("hash:1"), 3637660813);
+        assert_eq!(StoreSortKeyHash::from("hash:2"), 3577985381);
+    }
 }
@@ -26,6 +26,8 @@ enum StoreKeyerIdx<'a> {
     OIDToIID(StoreObjectOID<'a>),
     IIDToOID(StoreObjectIID),
     IIDToTerms(StoreObjectIID),
+    IIDToSorts(StoreObjectIID),
+    IIDToNGrams(StoreObjectIID),
 }
 
 pub type StoreKeyerKey = [u8; 9];
@@ -39,6 +41,8 @@ impl<'a> StoreKeyerIdx<'a> {
             StoreKeyerIdx::OIDToIID(_) => 2,
             StoreKeyerIdx::IIDToOID(_) => 3,
             StoreKeyerIdx::IIDToTerms(_) => 4,
+            StoreKeyerIdx::IIDToSorts(_) => 5,
+            StoreKeyerIdx::IIDToNGrams(_) => 6,
         }
     }
 }
@@ -64,6 +68,14 @@ impl StoreKeyerBuilder {
         Self::make(StoreKeyerIdx::IIDToTerms(iid), bucket)
     }
 
+    pub fn iid_to_sorts(bucket: &str, iid: StoreObjectIID) -> StoreKeyer {
+        Self::make(StoreKeyerIdx::IIDToSorts(iid), bucket)
+    }
+
+    pub fn iid_to_ngrams(bucket: &str, iid: StoreObjectIID) -> StoreKeyer {
+        Self::make(StoreKeyerIdx::IIDToNGrams(iid), bucket)
+    }
+
     fn make<'a>(idx: StoreKeyerIdx<'a>, bucket: &'a str) -> StoreKeyer {
         StoreKeyer {
             key: Self::build_key(idx, bucket),
@@ -103,6 +115,8 @@ impl StoreKeyerBuilder {
             StoreKeyerIdx::OIDToIID(route) => StoreKeyerHasher::to_compact(route),
             StoreKeyerIdx::IIDToOID(route) => *route,
             StoreKeyerIdx::IIDToTerms(route) => *route,
+            StoreKeyerIdx::IIDToSorts(route) => *route,
+            StoreKeyerIdx::IIDToNGrams(route) => *route,
         }
     }
 }
@@ -208,6 +222,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_keys_iid_to_sorts() {
+        assert_eq!(
+            StoreKeyerBuilder::iid_to_sorts("bucket:5", 1).as_bytes(),
+            [5, 137, 142, 73, 67, 1, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn it_keys_iid_to_ngrams() {
+        assert_eq!(
+            StoreKeyerBuilder::iid_to_ngrams("bucket:5", 1).as_bytes(),
+            [6, 137, 142, 73, 67, 1, 0, 0, 0]
+        );
+    }
+
     #[test]
     fn it_hashes_compact() {
         assert_eq!(StoreKeyerHasher::to_compact("key:1"), 3370353088);
@@ -277,4 +307,14 @@ mod benches {
     fn bench_key_iid_to_terms(b: &mut Bencher) {
         b.iter(|| StoreKeyerBuilder::iid_to_terms("bucket:bench:5", 1));
     }
+
+    #[bench]
+    fn bench_key_iid_to_sorts(b: &mut Bencher) {
+        b.iter(|| StoreKeyerBuilder::iid_to_sorts("bucket:bench:6", 1));
+    }
+
+    #[bench]
+    fn bench_key_iid_to_ngrams(b: &mut Bencher) {
+        b.iter(|| StoreKeyerBuilder::iid_to_ngrams("bucket:bench:7", 1));
+    }
 }
@@ -15,6 +15,7 @@ use rocksdb::{
     DBCompactionStyle, DBCompressionType, Error as DBError, FlushOptions, Options as DBOptions,
     WriteBatch, WriteOptions, DB,
 };
+use std::cell::RefCell;
 use std::fmt;
 use std::fs;
 use std::io::{self, Cursor};
@@ -23,7 +24,6 @@ use std::str;
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::SystemTime;
-use std::vec::Drain;
 
 use super::generic::{
     StoreGeneric, StoreGenericActionBuilder, StoreGenericBuilder, StoreGenericPool,
@@ -48,6 +48,7 @@ pub struct StoreKVActionBuilder;
 pub struct StoreKVAction<'a> {
     store: Option<StoreKVBox>,
     bucket: StoreItemPart<'a>,
+    write_batch: RefCell<Option<WriteBatch>>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
@@ -484,6 +485,10 @@ impl StoreKV {
         self.do_write(batch)
     }
 
+    pub fn write(&self, batch: WriteBatch) -> Result<(), DBError> {
+        self.do_write(batch)
+    }
+
     fn flush(&self) -> Result<(), DBError> {
         // Generate flush options
         let mut flush_options = FlushOptions::default();
@@ -530,7 +535,11 @@ impl StoreKVActionBuilder {
     }
 
     fn build(bucket: StoreItemPart, store: Option<StoreKVBox>) -> StoreKVAction {
-        StoreKVAction { store, bucket }
+        StoreKVAction {
+            store,
+            bucket,
+            write_batch: RefCell::new(None),
+        }
     }
 }
 
@@ -630,8 +639,7 @@ impl<'a> StoreKVAction<'a> {
                 StoreMetaValue::IIDIncr(iid_incr) => iid_incr.to_string(),
             };
 
-            store
-                .put(&store_key.as_bytes(), value_string.as_bytes())
+            self.put(store, &store_key.as_bytes(), value_string.as_bytes())
                 .or(Err(()))
         } else {
             Err(())
@@ -705,7 +713,8 @@ impl<'a> StoreKVAction<'a> {
                 store_key, iids_encoded
             );
 
-            store.put(&store_key.as_bytes(), &iids_encoded).or(Err(()))
+            self.put(store, &store_key.as_bytes(), &iids_encoded)
+                .or(Err(()))
         } else {
             Err(())
         }
@@ -717,7 +726,7 @@ impl<'a> StoreKVAction<'a> {
 
             debug!("store delete term-to-iids: {}", store_key);
 
-            store.delete(&store_key.as_bytes()).or(Err(()))
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
         } else {
             Err(())
         }
@@ -781,7 +790,8 @@ impl<'a> StoreKVAction<'a> {
                 store_key, iid_encoded
             );
 
-            store.put(&store_key.as_bytes(), &iid_encoded).or(Err(()))
+            self.put(store, &store_key.as_bytes(), &iid_encoded)
+                .or(Err(()))
         } else {
             Err(())
         }
@@ -793,7 +803,7 @@ impl<'a> StoreKVAction<'a> {
 
             debug!("store delete oid-to-iid: {}", store_key);
 
-            store.delete(&store_key.as_bytes()).or(Err(()))
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
         } else {
             Err(())
         }
@@ -824,7 +834,8 @@ impl<'a> StoreKVAction<'a> {
 
             debug!("store set iid-to-oid: {}", store_key);
 
-            store.put(&store_key.as_bytes(), oid.as_bytes()).or(Err(()))
+            self.put(store, &store_key.as_bytes(), oid.as_bytes())
+                .or(Err(()))
         } else {
             Err(())
         }
@@ -836,7 +847,7 @@ impl<'a> StoreKVAction<'a> {
 
             debug!("store delete iid-to-oid: {}", store_key);
 
-            store.delete(&store_key.as_bytes()).or(Err(()))
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
         } else {
             Err(())
         }
@@ -902,8 +913,7 @@ impl<'a> StoreKVAction<'a> {
                 store_key, terms_hashed_encoded
             );
 
-            store
-                .put(&store_key.as_bytes(), &terms_hashed_encoded)
+            self.put(store, &store_key.as_bytes(), &terms_hashed_encoded)
                 .or(Err(()))
         } else {
             Err(())
@@ -916,7 +926,191 @@ impl<'a> StoreKVAction<'a> {
 
             debug!("store delete iid-to-terms: {}", store_key);
 
-            store.delete(&store_key.as_bytes()).or(Err(()))
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
+        } else {
+            Err(())
+        }
+    }
+
+    /// IID-to-Sorts mapper
+    ///
+    /// [IDX=5] ((iid)) ~> [((sort key)), ((sort value))]
+    pub fn get_iid_to_sorts(
+        &self,
+        iid: StoreObjectIID,
+    ) -> Result<Option<Vec<(StoreSortKeyHashed, StoreSortValue)>>, ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_sorts(self.bucket.as_str(), iid);
+
+            debug!("store get iid-to-sorts: {}", store_key);
+
+            match store.get(&store_key.as_bytes()) {
+                Ok(Some(value)) => {
+                    debug!(
+                        "got iid-to-sorts: {} with encoded value: {:?}",
+                        store_key, &*value
+                    );
+
+                    Self::decode_sort_list(&*value)
+                        .or(Err(()))
+                        .map(|value_decoded| {
+                            debug!(
+                                "got iid-to-sorts: {} with decoded value: {:?}",
+                                store_key, &value_decoded
+                            );
+
+                            if !value_decoded.is_empty() {
+                                Some(value_decoded)
+                            } else {
+                                None
+                            }
+                        })
+                }
+                Ok(None) => Ok(None),
+                Err(_) => Err(()),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn set_iid_to_sorts(
+        &self,
+        iid: StoreObjectIID,
+        sorts: &[(StoreSortKeyHashed, StoreSortValue)],
+    ) -> Result<(), ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_sorts(self.bucket.as_str(), iid);
+
+            debug!("store set iid-to-sorts: {}", store_key);
+
+            // Encode sort list into storage serialized format
+            let sorts_encoded = Self::encode_sort_list(sorts);
+
+            debug!(
+                "store set iid-to-sorts: {} with encoded value: {:?}",
+                store_key, sorts_encoded
+            );
+
+            self.put(store, &store_key.as_bytes(), &sorts_encoded)
+                .or(Err(()))
+        } else {
+            Err(())
+        }
+    }
+
+    pub fn delete_iid_to_sorts(&self, iid: StoreObjectIID) -> Result<(), ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_sorts(self.bucket.as_str(), iid);
+
+            debug!("store delete iid-to-sorts: {}", store_key);
+
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
+        } else {
+            Err(())
+        }
+    }
+
+    /// IID-to-NGrams mapper
+    ///
+    /// [IDX=6] ((iid)) ~> [((ngram))]
+    pub fn get_iid_to_ngrams(
+        &self,
+        iid: StoreObjectIID,
+    ) -> Result<Option<Vec<StoreTermHashed>>, ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_ngrams(self.bucket.as_str(), iid);
+
+            debug!("store get iid-to-ngrams: {}", store_key);
+
+            match store.get(&store_key.as_bytes()) {
+                Ok(Some(value)) => {
+                    debug!(
+                        "got iid-to-ngrams: {} with encoded value: {:?}",
+                        store_key, &*value
+                    );
+
+                    Self::decode_u32_list(&*value)
+                        .or(Err(()))
+                        .map(|value_decoded| {
+                            debug!(
+                                "got iid-to-ngrams: {} with decoded value: {:?}",
+                                store_key, &value_decoded
+                            );
+
+                            if !value_decoded.is_empty() {
+                                Some(value_decoded)
+                            } else {
+                                None
+                            }
+                        })
+                }
+                Ok(None) => Ok(None),
+                Err(_) => Err(()),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn set_iid_to_ngrams(
+        &self,
+        iid: StoreObjectIID,
+        ngrams_hashed: &[StoreTermHashed],
+    ) -> Result<(), ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_ngrams(self.bucket.as_str(), iid);
+
+            debug!("store set iid-to-ngrams: {}", store_key);
+
+            // Encode n-gram list into storage serialized format
+            let ngrams_hashed_encoded = Self::encode_u32_list(ngrams_hashed);
+
+            debug!(
+                "store set iid-to-ngrams: {} with encoded value: {:?}",
+                store_key, ngrams_hashed_encoded
+            );
+
+            self.put(store, &store_key.as_bytes(), &ngrams_hashed_encoded)
+                .or(Err(()))
+        } else {
+            Err(())
+        }
+    }
+
+    pub fn delete_iid_to_ngrams(&self, iid: StoreObjectIID) -> Result<(), ()> {
+        if let Some(ref store) = self.store {
+            let store_key = StoreKeyerBuilder::iid_to_ngrams(self.bucket.as_str(), iid);
+
+            debug!("store delete iid-to-ngrams: {}", store_key);
+
+            self.delete(store, &store_key.as_bytes()).or(Err(()))
+        } else {
+            Err(())
+        }
+    }
+
+    /// Write batch
+    ///
+    /// Writes made while a write batch is open are staged, then committed all at once (or not \
+    ///   at all) when the write batch gets committed. Reads do not see staged writes.
+    pub fn begin_write_batch(&self) {
+        debug!("store begin write batch: {}", self.bucket.as_str());
+
+        *self.write_batch.borrow_mut() = Some(WriteBatch::default());
+    }
+
+    pub fn commit_write_batch(&self) -> Result<(), ()> {
+        let write_batch = self.write_batch.borrow_mut().take();
+
+        if let (Some(store), Some(write_batch)) = (self.store.as_ref(), write_batch) {
+            debug!(
+                "store commit write batch: {} with {} writes",
+                self.bucket.as_str(),
+                write_batch.len()
+            );
+
+            store.write(write_batch).or(Err(()))
         } else {
             Err(())
         }
@@ -935,45 +1129,75 @@ impl<'a> StoreKVAction<'a> {
             iid, iid_terms_hashed
         );
 
+        // Acquire n-grams linked to IID (if any; n-grams are not part of IID terms)
+        let mut iid_ngrams_hashed = self
+            .get_iid_to_ngrams(iid)
+            .unwrap_or(None)
+            .unwrap_or_default();
+
+        iid_ngrams_hashed.sort_unstable();
+        iid_ngrams_hashed.dedup();
+
         // Delete OID <> IID association
         match (
             self.delete_oid_to_iid(oid),
             self.delete_iid_to_oid(iid),
             self.delete_iid_to_terms(iid),
+            self.delete_iid_to_sorts(iid),
+            self.delete_iid_to_ngrams(iid),
         ) {
-            (Ok(_), Ok(_), Ok(_)) => {
+            (Ok(_), Ok(_), Ok(_), Ok(_), Ok(_)) => {
                 // Delete IID from each associated term
                 for iid_term in iid_terms_hashed {
-                    if let Ok(Some(mut iid_term_iids)) = self.get_term_to_iids(*iid_term) {
-                        if iid_term_iids.contains(&iid) {
-                            count += 1;
-
-                            // Remove IID from list of IIDs
-                            iid_term_iids.retain(|cur_iid| cur_iid != &iid);
-                        }
-
-                        let is_ok = if iid_term_iids.is_empty() {
-                            self.delete_term_to_iids(*iid_term).is_ok()
-                        } else {
-                            self.set_term_to_iids(*iid_term, &iid_term_iids).is_ok()
-                        };
-
-                        if !is_ok {
-                            return Err(());
-                        }
+                    if self.unlink_term_iid(*iid_term, iid)? {
+                        count += 1;
                     }
                 }
 
+                // Delete IID from each associated n-gram (n-grams are not counted as terms)
+                for iid_ngram in iid_ngrams_hashed {
+                    self.unlink_term_iid(iid_ngram, iid)?;
+                }
+
                 Ok(count)
             }
             _ => Err(()),
         }
     }
 
-    pub fn batch_truncate_object(
+    pub fn unlink_term_iid(
+        &self,
+        term_hashed: StoreTermHashed,
+        iid: StoreObjectIID,
+    ) -> Result<bool, ()> {
+        if let Ok(Some(mut term_iids)) = self.get_term_to_iids(term_hashed) {
+            let is_linked = term_iids.contains(&iid);
+
+            // Remove IID from list of IIDs
+            if is_linked {
+                term_iids.retain(|cur_iid| cur_iid != &iid);
+            }
+
+            let is_ok = if term_iids.is_empty() {
+                self.delete_term_to_iids(term_hashed).is_ok()
+            } else {
+                self.set_term_to_iids(term_hashed, &term_iids).is_ok()
+            };
+
+            if is_ok {
+                Ok(is_linked)
+            } else {
+                Err(())
+            }
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub fn batch_truncate_object<I: IntoIterator<Item = StoreObjectIID>>(
         &self,
         term_hashed: StoreTermHashed,
-        term_iids_drain: Drain<StoreObjectIID>,
+        term_iids_drain: I,
     ) -> Result<u32, ()> {
         let mut count = 0;
 
@@ -984,6 +1208,29 @@ impl<'a> StoreKVAction<'a> {
             if let Ok(Some(mut term_iid_drain_terms)) = self.get_iid_to_terms(term_iid_drain) {
                 count += 1;
 
+                // Term is an n-gram? Nuke it in IID to N-Grams list instead (as n-grams are not \
+                //   terms of the object, the object does not get flushed there)
+                if !term_iid_drain_terms.contains(&term_hashed) {
+                    if let Ok(Some(mut term_iid_drain_ngrams)) =
+                        self.get_iid_to_ngrams(term_iid_drain)
+                    {
+                        term_iid_drain_ngrams.retain(|cur_ngram| cur_ngram != &term_hashed);
+
+                        let is_ok = if term_iid_drain_ngrams.is_empty() {
+                            self.delete_iid_to_ngrams(term_iid_drain).is_ok()
+                        } else {
+                            self.set_iid_to_ngrams(term_iid_drain, &term_iid_drain_ngrams)
+                                .is_ok()
+                        };
+
+                        if !is_ok {
+                            error!("failed setting store batch truncate object iid-to-ngrams");
+                        }
+                    }
+
+                    continue;
+                }
+
                 term_iid_drain_terms.retain(|cur_term| cur_term != &term_hashed);
 
                 // IID to Terms list is empty? Flush whole object.
@@ -1019,20 +1266,32 @@ impl<'a> StoreKVAction<'a> {
     pub fn batch_erase_bucket(&self) -> Result<u32, ()> {
         if let Some(ref store) = self.store {
             // Generate all key prefix values (with dummy post-prefix values; we dont care)
-            let (k_meta_to_value, k_term_to_iids, k_oid_to_iid, k_iid_to_oid, k_iid_to_terms) = (
+            let (
+                k_meta_to_value,
+                k_term_to_iids,
+                k_oid_to_iid,
+                k_iid_to_oid,
+                k_iid_to_terms,
+                k_iid_to_sorts,
+                k_iid_to_ngrams,
+            ) = (
                 StoreKeyerBuilder::meta_to_value(self.bucket.as_str(), &StoreMetaKey::IIDIncr),
                 StoreKeyerBuilder::term_to_iids(self.bucket.as_str(), 0),
                 StoreKeyerBuilder::oid_to_iid(self.bucket.as_str(), &String::new()),
                 StoreKeyerBuilder::iid_to_oid(self.bucket.as_str(), 0),
                 StoreKeyerBuilder::iid_to_terms(self.bucket.as_str(), 0),
+                StoreKeyerBuilder::iid_to_sorts(self.bucket.as_str(), 0),
+                StoreKeyerBuilder::iid_to_ngrams(self.bucket.as_str(), 0),
             );
 
-            let key_prefixes: [StoreKeyerPrefix; 5] = [
+            let key_prefixes: [StoreKeyerPrefix; 7] = [
                 k_meta_to_value.as_prefix(),
                 k_term_to_iids.as_prefix(),
                 k_oid_to_iid.as_prefix(),
                 k_iid_to_oid.as_prefix(),
                 k_iid_to_terms.as_prefix(),
+                k_iid_to_sorts.as_prefix(),
+                k_iid_to_ngrams.as_prefix(),
             ];
 
             // Scan all keys per-prefix and nuke them right away
@@ -1104,6 +1363,28 @@ impl<'a> StoreKVAction<'a> {
         }
     }
 
+    fn put(&self, store: &StoreKV, key: &[u8], data: &[u8]) -> Result<(), DBError> {
+        // Stage write in open write batch? (otherwise, write now)
+        if let Some(ref mut write_batch) = *self.write_batch.borrow_mut() {
+            write_batch.put(key, data);
+
+            Ok(())
+        } else {
+            store.put(key, data)
+        }
+    }
+
+    fn delete(&self, store: &StoreKV, key: &[u8]) -> Result<(), DBError> {
+        // Stage delete in open write batch? (otherwise, delete now)
+        if let Some(ref mut write_batch) = *self.write_batch.borrow_mut() {
+            write_batch.delete(key);
+
+            Ok(())
+        } else {
+            store.delete(key)
+        }
+    }
+
     fn encode_u32(decoded: u32) -> [u8; 4] {
         let mut encoded = [0; 4];
 
@@ -1143,6 +1424,40 @@ impl<'a> StoreKVAction<'a> {
 
         Ok(decoded)
     }
+
+    fn encode_sort_list(decoded: &[(StoreSortKeyHashed, StoreSortValue)]) -> Vec<u8> {
+        // Notice: each sort is encoded as a 32 bits key hash, followed by a signed 64 bits value
+        let mut encoded = Vec::with_capacity(decoded.len() * 12);
+
+        for (decoded_key, decoded_value) in decoded {
+            let mut encoded_value = [0; 8];
+
+            LittleEndian::write_i64(&mut encoded_value, *decoded_value);
+
+            encoded.extend(&Self::encode_u32(*decoded_key));
+            encoded.extend(&encoded_value);
+        }
+
+        encoded
+    }
+
+    fn decode_sort_list(encoded: &[u8]) -> Result<Vec<(StoreSortKeyHashed, StoreSortValue)>, ()> {
+        let mut decoded = Vec::with_capacity(encoded.len() / 12);
+
+        for encoded_chunk in encoded.chunks(12) {
+            let mut cursor = Cursor::new(encoded_chunk);
+
+            match (
+                cursor.read_u32::<LittleEndian>(),
+                cursor.read_i64::<LittleEndian>(),
+            ) {
+                (Ok(decoded_key), Ok(decoded_value)) => decoded.push((decoded_key, decoded_value)),
+                _ => return Err(()),
+            }
+        }
+
+        Ok(decoded)
+    }
 }
 
 impl StoreKVKey {
@@ -1214,6 +1529,14 @@ mod tests {
         assert!(action.get_iid_to_terms(4).is_ok());
         assert!(action.set_iid_to_terms(4, &[45402]).is_ok());
         assert!(action.delete_iid_to_terms(4).is_ok());
+
+        assert!(action.get_iid_to_sorts(4).is_ok());
+        assert!(action.set_iid_to_sorts(4, &[(45402, -1)]).is_ok());
+        assert!(action.delete_iid_to_sorts(4).is_ok());
+
+        assert!(action.get_iid_to_ngrams(4).is_ok());
+        assert!(action.set_iid_to_ngrams(4, &[45402, 45402, 7]).is_ok());
+        assert!(action.delete_iid_to_ngrams(4).is_ok());
     }
 
     #[test]
@@ -1250,6 +1573,31 @@ mod tests {
             Ok(vec![45402])
         );
     }
+
+    #[test]
+    fn it_encodes_sort_list() {
+        assert_eq!(
+            StoreKVAction::encode_sort_list(&[(45402, 1)]),
+            [90, 177, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            StoreKVAction::encode_sort_list(&[(1, -1)]),
+            [1, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn it_decodes_sort_list() {
+        assert_eq!(
+            StoreKVAction::decode_sort_list(&[90, 177, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]),
+            Ok(vec![(45402, 1)])
+        );
+        assert_eq!(
+            StoreKVAction::decode_sort_list(&[1, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255]),
+            Ok(vec![(1, -1)])
+        );
+        assert!(StoreKVAction::decode_sort_list(&[1, 0, 0, 0, 255]).is_err());
+    }
 }
 
 #[cfg(all(feature = "benchmark", test))]
@@ -8,10 +8,10 @@
 mod macros;
 
 mod generic;
-mod keyer;
 
 pub mod fst;
 pub mod identifiers;
 pub mod item;
+pub mod keyer;
 pub mod kv;
 pub mod operation;
@@ -4,6 +4,7 @@
 // Copyright: 2019, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use crate::executor::correct::ExecutorCorrect;
 use crate::executor::count::ExecutorCount;
 use crate::executor::flushb::ExecutorFlushB;
 use crate::executor::flushc::ExecutorFlushC;
@@ -11,9 +12,12 @@ use crate::executor::flusho::ExecutorFlushO;
 use crate::executor::list::ExecutorList;
 use crate::executor::pop::ExecutorPop;
 use crate::executor::push::ExecutorPush;
+use crate::executor::replace::ExecutorReplace;
 use crate::executor::search::ExecutorSearch;
 use crate::executor::suggest::ExecutorSuggest;
 use crate::query::actions::Query;
+use crate::query::cache::QueryCache;
+use crate::store::item::StoreItemPart;
 
 pub struct StoreOperationDispatch;
 
@@ -21,35 +25,87 @@ impl StoreOperationDispatch {
     pub fn dispatch(query: Query) -> Result<Option<String>, ()> {
         // Dispatch de-constructed query to its target executor
         match query {
-            Query::Search(store, query_id, lexer, limit, offset) => {
-                ExecutorSearch::execute(store, query_id, lexer, limit, offset)
+            Query::Search(store, query_id, lexer, options) => {
+                ExecutorSearch::execute(store, query_id, lexer, options)
                     .map(|results| results.map(|results| results.join(" ")))
             }
+            Query::Explain(store, query_id, lexer, options) => {
+                ExecutorSearch::explain(store, query_id, lexer, options)
+            }
             Query::Suggest(store, query_id, lexer, limit) => {
                 ExecutorSuggest::execute(store, query_id, lexer, limit)
                     .map(|results| results.map(|results| results.join(" ")))
             }
-            Query::List(store, query_id, limit, offset) => {
-                ExecutorList::execute(store, query_id, limit, offset)
+            Query::Correct(store, query_id, lexer) => {
+                ExecutorCorrect::execute(store, query_id, lexer)
+            }
+            Query::List(store, query_id, options) => {
+                ExecutorList::execute(store, query_id, options)
                     .map(|results| results.join(" "))
                     .map(|results| Some(results))
             }
-            Query::Push(store, lexer) => ExecutorPush::execute(store, lexer).map(|_| None),
+            Query::Push(store, lexer, sorts) => {
+                let (collection, bucket) = (store.0, store.1);
+                let results = ExecutorPush::execute(store, lexer, sorts).map(|_| None);
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
+            }
             Query::Pop(store, lexer) => {
-                ExecutorPop::execute(store, lexer).map(|count| Some(count.to_string()))
+                let (collection, bucket) = (store.0, store.1);
+                let results =
+                    ExecutorPop::execute(store, lexer).map(|count| Some(count.to_string()));
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
+            }
+            Query::Replace(store, lexer, sorts) => {
+                let (collection, bucket) = (store.0, store.1);
+                let results = ExecutorReplace::execute(store, lexer, sorts).map(|_| None);
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
             }
             Query::Count(store) => {
                 ExecutorCount::execute(store).map(|count| Some(count.to_string()))
             }
             Query::FlushC(store) => {
-                ExecutorFlushC::execute(store).map(|count| Some(count.to_string()))
+                let (collection, bucket) = (store.0, store.1);
+                let results = ExecutorFlushC::execute(store).map(|count| Some(count.to_string()));
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
             }
             Query::FlushB(store) => {
-                ExecutorFlushB::execute(store).map(|count| Some(count.to_string()))
+                let (collection, bucket) = (store.0, store.1);
+                let results = ExecutorFlushB::execute(store).map(|count| Some(count.to_string()));
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
             }
             Query::FlushO(store) => {
-                ExecutorFlushO::execute(store).map(|count| Some(count.to_string()))
+                let (collection, bucket) = (store.0, store.1);
+                let results = ExecutorFlushO::execute(store).map(|count| Some(count.to_string()));
+
+                Self::invalidate_cache(collection, bucket);
+
+                results
             }
         }
     }
+
+    fn invalidate_cache(collection: StoreItemPart, bucket: Option<StoreItemPart>) {
+        // Cached query results for mutated bucket (or collection) are outdated (this must be \
+        //   done after mutation, as to prevent concurrent searches from caching old results)
+        if let Some(bucket) = bucket {
+            QueryCache::invalidate_bucket(collection.as_str(), bucket.as_str());
+        } else {
+            QueryCache::invalidate_collection(collection.as_str());
+        }
+    }
 }